@@ -0,0 +1,184 @@
+//! Algoritmi a stima di distribuzione (PBIL e UMDA) su array bilanciati.
+//! Il modello tiene, per ogni cella, la probabilità che valga 1; ogni
+//! colonna campionata ha esattamente N/2 uni, scelti tra le righe con un
+//! campionamento pesato senza ripetizione (Efraimidis-Spirakis), quindi gli
+//! array sono bilanciati per costruzione. Il modello si aggiorna con la
+//! frequenza degli uni nell'élite.
+
+use oarray::{FitnessCache, FitnessFunction, OArray};
+use pbr::ProgressBar;
+use rand::{thread_rng, Rng};
+use run::RunParameters;
+use std::cmp::Ordering;
+use std::f64;
+
+/// Probabilità minima e massima di ogni cella, per non fissare il modello
+const MARGIN: f64 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eda {
+    /// 1.0 per UMDA: il modello è ricalcolato da zero sull'élite
+    pub learning_rate: f64,
+    /// numero di array migliori usati per aggiornare il modello
+    pub elite: usize,
+}
+
+impl Eda {
+    /// Costruisce l'algoritmo dai nomi usati sulla linea di comando:
+    /// `none`, `pbil` o `umda`
+    pub fn from_name(name: &str, learning_rate: f64, elite: usize) -> Result<Option<Self>, String> {
+        let learning_rate = match name {
+            "none" => return Ok(None),
+            "pbil" => learning_rate,
+            "umda" => 1.0,
+            _ => return Err(format!("Unknown EDA {}", name)),
+        };
+        if learning_rate <= 0.0 || learning_rate > 1.0 {
+            return Err("The learning rate must be in (0, 1]".to_string());
+        }
+        if elite == 0 {
+            return Err("The elite must not be empty".to_string());
+        }
+        Ok(Some(Eda {
+            learning_rate,
+            elite,
+        }))
+    }
+}
+
+/// Probabilità degli uni cella per cella, nello stesso ordine di `OArray::d`
+#[derive(Debug, Clone)]
+pub struct BalancedModel {
+    ngrande: usize,
+    k: usize,
+    p: Vec<f64>,
+}
+
+impl BalancedModel {
+    /// Modello uniforme: ogni colonna bilanciata ha la stessa probabilità
+    pub fn new(ngrande: usize, k: usize) -> Self {
+        BalancedModel {
+            ngrande,
+            k,
+            p: vec![0.5; ngrande * k],
+        }
+    }
+
+    pub fn sample(&self, target_t: u32, fitness_f: FitnessFunction, r: &mut impl Rng) -> OArray {
+        let mut d = vec![false; self.ngrande * self.k];
+        for (col, probs) in d.chunks_mut(self.ngrande).zip(self.p.chunks(self.ngrande)) {
+            sample_column(col, probs, r);
+        }
+        OArray::new(self.ngrande, self.k, target_t, d, fitness_f)
+    }
+
+    /// p = (1 - rate) * p + rate * frequenza degli uni nell'élite
+    pub fn update(&mut self, elite: &[&OArray], rate: f64) {
+        let n = elite.len() as f64;
+        for (i, p) in self.p.iter_mut().enumerate() {
+            let freq = elite.iter().filter(|oa| oa.d[i]).count() as f64 / n;
+            *p = ((1.0 - rate) * *p + rate * freq).clamp(MARGIN, 1.0 - MARGIN);
+        }
+    }
+
+    /// Entropia media delle celle, in bit: 1 per il modello uniforme
+    pub fn entropy(&self) -> f64 {
+        self.p
+            .iter()
+            .map(|&p| -p * p.log2() - (1.0 - p) * (1.0 - p).log2())
+            .sum::<f64>()
+            / self.p.len() as f64
+    }
+}
+
+/// Mette a 1 metà delle righe: le chiavi ln(u) / p più grandi
+fn sample_column(col: &mut [bool], probs: &[f64], r: &mut impl Rng) {
+    let mut keys: Vec<(f64, usize)> = probs
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (r.gen::<f64>().ln() / p, i))
+        .collect();
+    keys.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    for &(_, i) in keys.iter().take(col.len() / 2) {
+        col[i] = true;
+    }
+}
+
+pub(crate) fn run(p: &RunParameters, eda: &Eda, show_progress: bool) -> (bool, bool) {
+    let mut rng = thread_rng();
+    let cache = FitnessCache::new(p.cache_size);
+    let mut model = BalancedModel::new(p.ngrande, p.k);
+    let mut best: Option<(OArray, f64)> = None;
+
+    let mut pbar = ProgressBar::new(p.epochs as u64);
+    for _ in 0..p.epochs {
+        let mut samples: Vec<(OArray, f64)> = (0..p.pop_size)
+            .map(|_| {
+                let oa = model.sample(p.t, p.fitness_f, &mut rng);
+                let f = cache.get_or_insert_with(&oa.d, || oa.fitness());
+                (oa, f)
+            })
+            .collect();
+        samples.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        if best.as_ref().is_none_or(|b| samples[0].1 > b.1) {
+            best = Some(samples[0].clone());
+        }
+        let elite: Vec<&OArray> = samples.iter().take(eda.elite).map(|x| &x.0).collect();
+        model.update(&elite, eda.learning_rate);
+        if show_progress {
+            pbar.message(&format!(
+                " Best: {:.4}, Entropy: {:.4}; iteration ",
+                best.as_ref().unwrap().1,
+                model.entropy()
+            ));
+            pbar.inc();
+        }
+        if -best.as_ref().unwrap().1 < f64::EPSILON {
+            break;
+        }
+    }
+    debug!("Fitness cache: {}", cache);
+    match best {
+        Some((ref oa, f)) if -f < f64::EPSILON => {
+            debug!("{}", oa);
+            (true, oa.check_linear())
+        }
+        _ => (false, false),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert!(Eda::from_name("pbil", 0.1, 5).unwrap().unwrap().learning_rate == 0.1);
+        assert!(Eda::from_name("umda", 0.1, 5).unwrap().unwrap().learning_rate == 1.0);
+        assert!(Eda::from_name("none", 0.1, 5) == Ok(None));
+        assert!(Eda::from_name("pbil", 0.0, 5).is_err());
+        assert!(Eda::from_name("pbil", 0.1, 0).is_err());
+        assert!(Eda::from_name("cma", 0.1, 5).is_err());
+    }
+
+    #[test]
+    fn test_model() {
+        let mut r = thread_rng();
+        let mut model = BalancedModel::new(8, 3);
+        assert!((model.entropy() - 1.0).abs() < 1e-9);
+        let mut col = vec![false; 8];
+        sample_column(&mut col, &model.p[0..8], &mut r);
+        assert!(col.iter().filter(|&&x| x).count() == 4);
+        //un'élite fatta di un solo array porta il modello verso di esso
+        let d: Vec<bool> = (0..24).map(|i| i % 8 < 4).collect();
+        let target = OArray::new(8, 3, 2, d, FitnessFunction::DeltaFast);
+        for _ in 0..10 {
+            model.update(&[&target], 0.5);
+        }
+        assert!(model.entropy() < 0.2);
+        assert!(model.p.iter().zip(target.d.iter()).all(|(&p, &x)| (p > 0.5) == x));
+        let mut d = vec![false; 8];
+        sample_column(&mut d, &model.p[0..8], &mut r);
+        assert!(d.iter().filter(|&&x| x).count() == 4);
+    }
+}
@@ -0,0 +1,163 @@
+//! Strategie evolutive (1+1), (1+λ) e (μ+λ) su array bilanciati, come
+//! termine di paragone per l'algoritmo genetico: nessun crossover, ogni
+//! figlio è una copia di un genitore a cui si applicano scambi in colonne
+//! scelte a caso.
+
+use ga_common::mutation::swap_different;
+use ga_common::SwapCount;
+use oarray::{FitnessCache, OArray};
+use pbr::ProgressBar;
+use rand::{thread_rng, Rng};
+use run::RunParameters;
+use std::cmp::Ordering;
+use std::f64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvolutionStrategy {
+    pub mu: usize,
+    pub lambda: usize,
+    /// numero di scambi per figlio; `PowerLaw` dà la mutazione del "fast GA"
+    pub strength: SwapCount,
+}
+
+impl EvolutionStrategy {
+    /// Costruisce la strategia dai nomi usati sulla linea di comando:
+    /// `none`, `1+1`, `1+lambda` o `mu+lambda`
+    pub fn from_name(
+        name: &str,
+        mu: usize,
+        lambda: usize,
+        strength: SwapCount,
+    ) -> Result<Option<Self>, String> {
+        let (mu, lambda) = match name {
+            "none" => return Ok(None),
+            "1+1" => (1, 1),
+            "1+lambda" => (1, lambda),
+            "mu+lambda" => (mu, lambda),
+            _ => return Err(format!("Unknown evolution strategy {}", name)),
+        };
+        if mu == 0 || lambda == 0 {
+            return Err("mu and lambda must be positive".to_string());
+        }
+        Ok(Some(EvolutionStrategy {
+            mu,
+            lambda,
+            strength,
+        }))
+    }
+
+    /// Figlio di `parent` con un numero di scambi estratto da `strength`
+    fn mutate(&self, parent: &OArray, r: &mut impl Rng) -> OArray {
+        let mut child = parent.clone();
+        let ngrande = child.ngrande;
+        let swaps = self.strength.sample(ngrande * child.k / 2, r);
+        for _ in 0..swaps {
+            let col = r.gen_range(0, child.k);
+            swap_different(&mut child.d[col * ngrande..(col + 1) * ngrande], r);
+        }
+        child
+    }
+
+    /// Una generazione: λ figli da genitori scelti a caso, poi i μ migliori
+    /// tra genitori e figli. A parità di fitness i figli passano davanti,
+    /// così la ricerca può muoversi sui plateau.
+    fn generation(
+        &self,
+        parents: Vec<(OArray, f64)>,
+        eval: &impl Fn(&OArray) -> f64,
+        r: &mut impl Rng,
+    ) -> Vec<(OArray, f64)> {
+        let mut pool: Vec<(OArray, f64)> = (0..self.lambda)
+            .map(|_| {
+                let child = self.mutate(&parents[r.gen_range(0, parents.len())].0, r);
+                let f = eval(&child);
+                (child, f)
+            })
+            .collect();
+        pool.extend(parents);
+        //sort_by è stabile: i figli restano prima dei genitori con la stessa fitness
+        pool.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+        pool.truncate(self.mu);
+        pool
+    }
+}
+
+pub(crate) fn run(p: &RunParameters, es: &EvolutionStrategy, show_progress: bool) -> (bool, bool) {
+    let mut rng = thread_rng();
+    let cache = FitnessCache::new(p.cache_size);
+    let eval = |oa: &OArray| cache.get_or_insert_with(&oa.d, || oa.fitness());
+    let mut parents: Vec<(OArray, f64)> = (0..es.mu)
+        .map(|_| {
+            let oa = OArray::new_random_balanced(p.ngrande, p.k, p.t, &mut rng, p.fitness_f);
+            let f = eval(&oa);
+            (oa, f)
+        })
+        .collect();
+    parents.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal));
+
+    let mut pbar = ProgressBar::new(p.epochs as u64);
+    for _ in 0..p.epochs {
+        if -parents[0].1 < f64::EPSILON {
+            break;
+        }
+        parents = es.generation(parents, &eval, &mut rng);
+        if show_progress {
+            let mean = parents.iter().map(|x| x.1).sum::<f64>() / parents.len() as f64;
+            pbar.message(&format!(" Best: {:.4}, Mean: {:.4}; iteration ", parents[0].1, mean));
+            pbar.inc();
+        }
+    }
+    debug!("Fitness cache: {}", cache);
+    let best = &parents[0];
+    if -best.1 < f64::EPSILON {
+        debug!("{}", best.0);
+        (true, best.0.check_linear())
+    } else {
+        (false, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oarray::FitnessFunction;
+
+    #[test]
+    fn test_from_name() {
+        let fixed = SwapCount::Fixed(1);
+        let es = EvolutionStrategy::from_name("1+lambda", 5, 8, fixed).unwrap().unwrap();
+        assert!(es.mu == 1 && es.lambda == 8);
+        let es = EvolutionStrategy::from_name("mu+lambda", 5, 8, fixed).unwrap().unwrap();
+        assert!(es.mu == 5 && es.lambda == 8);
+        assert!(EvolutionStrategy::from_name("none", 5, 8, fixed) == Ok(None));
+        assert!(EvolutionStrategy::from_name("mu,lambda", 5, 8, fixed).is_err());
+        assert!(EvolutionStrategy::from_name("mu+lambda", 0, 8, fixed).is_err());
+    }
+
+    #[test]
+    fn test_generation() {
+        let mut r = thread_rng();
+        let es = EvolutionStrategy {
+            mu: 3,
+            lambda: 6,
+            strength: SwapCount::PowerLaw(1.5),
+        };
+        let eval = |oa: &OArray| oa.fitness();
+        let mut parents: Vec<(OArray, f64)> = (0..3)
+            .map(|_| {
+                let oa = OArray::new_random_balanced(16, 5, 2, &mut r, FitnessFunction::DeltaFast);
+                let f = oa.fitness();
+                (oa, f)
+            })
+            .collect();
+        for _ in 0..20 {
+            let best = parents.iter().map(|x| x.1).fold(f64::NEG_INFINITY, f64::max);
+            parents = es.generation(parents, &eval, &mut r);
+            assert!(parents.len() == 3);
+            assert!(parents[0].1 >= best);
+            for (oa, _) in &parents {
+                assert!(oa.iter_cols().all(|c| 2 * c.iter().filter(|&&x| x).count() == 16));
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use oarray::OArray;
+use ga_common::{BalancedCrossover, Genotype, Mutator, RateAdaptation};
+use memetic::Memetic;
+use oarray::{FitnessCache, OArray};
 use rand::thread_rng;
 use rand::Rng;
 use spiril::unit::Unit;
 use std::iter;
+use std::sync::Arc;
 
 /// Istanza di OArray dedicata all'algoritmo genetico: implementa
 /// la mutazione.
@@ -10,45 +13,21 @@ use std::iter;
 pub struct GAOArray {
     pub oa: OArray,
     pub mutation_prob: f64,
+    pub crossover: BalancedCrossover,
+    pub mutator: Mutator,
+    /// fitness già calcolate, condivise da tutta la popolazione
+    pub cache: Arc<FitnessCache>,
+    /// ricerca locale applicata ai figli, se presente
+    pub memetic: Option<Memetic>,
+    /// fitness dopo la ricerca locale baldwiniana
+    pub learned_fitness: Option<f64>,
 }
 
 impl GAOArray {
-    /// Scambia due coordinate nel vettore con probabiltà `prob`,
-    /// usando `rng`.
+    /// Muta ogni colonna con probabilità `prob`, usando `rng`.
     fn mutate_with_prob(&mut self, prob: f64, rng: &mut impl Rng) {
         let n = self.oa.ngrande;
-        for col in self.oa.iter_cols_mut() {
-            if rng.gen_range::<f64>(0.0, 1.0) < prob {
-                //pick random coordinate to mutate
-                let coord1 = rng.gen_range(0, n);
-                //pick other coordinate to swap
-                let mut coord2 = rng.gen_range(0, n);
-                while col[coord2] == col[coord1] {
-                    coord2 = rng.gen_range(0, n);
-                }
-                col.swap(coord1, coord2);
-            }
-        }
-    }
-}
-/// Unisce due OArray in in modo che il risultato sia bilanciato
-fn balanced_crossover(a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
-    let ngrande = a.len();
-    let balance = ngrande / 2;
-    let mut pos: Vec<_> = (0..ngrande).collect();
-    r.shuffle(&mut pos);
-    let mut cnt = [balance, balance];
-    for j in pos {
-        let choice = if cnt[0] == 0 {
-            true
-        } else if cnt[1] == 0 {
-            false
-        } else {
-            *r.choose(&[a[j], b[j]]).unwrap()
-        };
-
-        out[j] = choice;
-        cnt[choice as usize] -= 1;
+        self.mutator.mutate(&mut self.oa.d, n, prob, rng);
     }
 }
 
@@ -67,33 +46,67 @@ impl Unit for GAOArray {
             iter::repeat(false).take(oa.ngrande * oa.k).collect(),
             oa.fitness_f
         );
-        for (col1, (col2, col3)) in oa
-            .iter_cols()
-            .zip(other.oa.iter_cols().zip(out_inner.iter_cols_mut()))
-        {
-            balanced_crossover(col1, col2, col3, &mut rng);
-        }
+        self.crossover
+            .apply(&oa.d, &other.oa.d, &mut out_inner.d, oa.ngrande, &mut rng);
+        let mutation_prob = self.mutator.child_rate(self.mutation_prob, &mut rng);
         let mut out = GAOArray {
             oa: out_inner,
-            mutation_prob: self.mutation_prob,
+            mutation_prob,
+            crossover: self.crossover,
+            mutator: self.mutator.clone(),
+            cache: self.cache.clone(),
+            memetic: self.memetic,
+            learned_fitness: None,
         };
-        out.mutate_with_prob(self.mutation_prob, &mut rng);
+        out.mutate_with_prob(mutation_prob, &mut rng);
+        if let Some(memetic) = self.memetic {
+            out.refine(&memetic, &mut rng);
+        }
+        if self.mutator.adaptation == RateAdaptation::OneFifth {
+            let best_parent = self.fitness().max(other.fitness());
+            self.mutator.report(out.fitness() > best_parent);
+        }
         out
     }
 
     /// Fitness: calcola delta_grande per ogni combinazione di colonne e somma
     fn fitness(&self) -> f64 {
-        self.oa.fitness()
+        if let Some(f) = self.learned_fitness {
+            return f;
+        }
+        self.cache
+            .get_or_insert_with(&self.oa.d, || self.oa.fitness())
+    }
+}
+
+impl Genotype for GAOArray {
+    fn genotype(&self) -> &[bool] {
+        &self.oa.d
+    }
+    fn col_len(&self) -> usize {
+        self.oa.ngrande
     }
 }
+
+/// Unità di prova su un array bilanciato casuale, con gli operatori di default
+#[cfg(test)]
+pub(crate) fn test_unit(ngrande: usize, k: usize, t: u32, r: &mut impl Rng) -> GAOArray {
+    use oarray::FitnessFunction;
+    GAOArray {
+        oa: OArray::new_random_balanced(ngrande, k, t, r, FitnessFunction::DeltaFast),
+        mutation_prob: 0.5,
+        crossover: BalancedCrossover::Uniform,
+        mutator: Mutator::default(),
+        cache: Arc::new(FitnessCache::new(0)),
+        memetic: None,
+        learned_fitness: None,
+    }
+}
+
 #[test]
 fn mutation() {
-    use oarray::FitnessFunction;
     let mut r = thread_rng();
-    let mut a = GAOArray {
-        oa: OArray::new_random_balanced(8, 4, 3, &mut r, FitnessFunction::DeltaFast),
-        mutation_prob: 0.5,
-    };
+    let mut a = test_unit(8, 4, 3, &mut r);
     let b = a.clone();
     assert!(a.oa.d == b.oa.d);
     a.mutate_with_prob(1.0, &mut r);
@@ -105,21 +118,12 @@ fn mutation() {
 
 #[test]
 fn balanced_crossover_test() {
-    use oarray::FitnessFunction;
+    use ga_common::crossover::balanced_crossover;
     let mut r = thread_rng();
     for _ in 0..100 {
-        let a = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
-        let b = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
-        let mut c = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
+        let a = test_unit(8, 1, 1, &mut r);
+        let b = test_unit(8, 1, 1, &mut r);
+        let mut c = test_unit(8, 1, 1, &mut r);
         assert!(is_balanced(&a.oa.d));
         assert!(is_balanced(&b.oa.d));
         balanced_crossover(&a.oa.d, &b.oa.d, &mut c.oa.d, &mut r);
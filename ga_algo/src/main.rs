@@ -1,4 +1,5 @@
 extern crate clap;
+extern crate ga_common;
 extern crate oarray;
 extern crate pbr;
 extern crate rand;
@@ -8,14 +9,23 @@ extern crate log;
 extern crate simplelog;
 
 use clap::{App, Arg};
+use ga_common::{
+    BalancedCrossover, BalancedMutation, ConfigurableEpoch, IslandParameters, RateAdaptation,
+    StagnationParameters, SwapCount, Topology,
+};
+use memetic::Learning;
+use oarray::LocalSearch;
 use simplelog::*;
 use std::fs::File;
 use std::thread;
 
+mod eda;
+mod es;
 mod genetic_operators;
+mod memetic;
+mod nsga2;
 mod run;
 use run::run;
-mod epoch;
 
 macro_rules! get_arg {
     ($matches: expr, $x:expr, $type: ident) => {
@@ -28,6 +38,8 @@ macro_rules! get_arg {
 }
 
 fn main() {
+    ga_common::register_custom_fitness();
+    let fitness_help = format!("Fitness function [{}]", oarray::fitness_names().join(", "));
     let matches = App::new("GA run")
         .about("Run the Genetic Algorithm")
         .arg(
@@ -96,7 +108,7 @@ fn main() {
         .arg(
             Arg::with_name("fitness")
                 .long("fitness")
-                .help("Fitness function [Delta, DeltaFast, Walsh]")
+                .help(&fitness_help)
                 .default_value("DeltaFast"),
         )
         .arg(
@@ -105,20 +117,217 @@ fn main() {
                 .help("Exponent for the fitness function")
                 .default_value("2"),
         )
+        .arg(
+            Arg::with_name("multi-objective")
+                .long("multi-objective")
+                .help("Use NSGA-II to optimise fitness and nonlinearity, printing the Pareto front")
+                .default_value("false"),
+        )
+        .arg(
+            Arg::with_name("mo-degree")
+                .long("mo-degree")
+                .help("Add the algebraic degree to the multi-objective mode objectives")
+                .default_value("false"),
+        )
+        .arg(
+            Arg::with_name("cache-size")
+                .long("cache-size")
+                .help("Maximum number of cached fitness values per run (0 disables the cache)")
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::with_name("islands")
+                .long("islands")
+                .help("Number of islands (subpopulations of pop-size units, one thread each) per run")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("topology")
+                .long("topology")
+                .help("Migration topology between islands [ring, full]")
+                .default_value("ring"),
+        )
+        .arg(
+            Arg::with_name("migration-interval")
+                .long("migration-interval")
+                .help("Epochs between two migrations")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::with_name("migration-size")
+                .long("migration-size")
+                .help("Number of best units sent to each neighbouring island")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::with_name("selection")
+                .long("selection")
+                .help("Parent selection scheme [truncation, tournament, roulette, rank]")
+                .default_value("truncation"),
+        )
+        .arg(
+            Arg::with_name("tournament-size")
+                .long("tournament-size")
+                .help("Number of units competing in each tournament")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("replacement")
+                .long("replacement")
+                .help("Replacement scheme [survivors, generational, plus, comma, steady-state]")
+                .default_value("survivors"),
+        )
+        .arg(
+            Arg::with_name("offspring")
+                .long("offspring")
+                .help("Offspring per epoch for plus, comma and steady-state (0: pop-size)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("crossover")
+                .long("crossover")
+                .help("Balanced crossover [uniform, one-point, two-point, map-of-ones, column-exchange, row-block]")
+                .default_value("uniform"),
+        )
+        .arg(
+            Arg::with_name("mutation")
+                .long("mutation")
+                .help("Mutation applied to each column [swap, inversion, scramble, rerandomize]")
+                .default_value("swap"),
+        )
+        .arg(
+            Arg::with_name("swap-count")
+                .long("swap-count")
+                .help("Swaps per swap mutation: n, uniform:n (1..=n), poisson:lambda or power:beta")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("rate-adaptation")
+                .long("rate-adaptation")
+                .help("Mutation probability adaptation [fixed, one-fifth, self-adaptive]")
+                .default_value("fixed"),
+        )
+        .arg(
+            Arg::with_name("stagnation")
+                .long("stagnation")
+                .help("Epochs without improvement before a restart (0: never restart)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("restart")
+                .long("restart")
+                .help("Restart on stagnation [partial, full]")
+                .default_value("partial"),
+        )
+        .arg(
+            Arg::with_name("restart-fraction")
+                .long("restart-fraction")
+                .help("Fraction of the worst units re-initialised by a partial restart")
+                .default_value("0.5"),
+        )
+        .arg(
+            Arg::with_name("elite")
+                .long("elite")
+                .help("Number of best units kept by a restart")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("diversity-every")
+                .long("diversity-every")
+                .help("Log the population diversity every n epochs (0: never)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("local-search")
+                .long("local-search")
+                .help("Local search refining the offspring by Walsh spectrum swaps [none, first, steepest]")
+                .default_value("none"),
+        )
+        .arg(
+            Arg::with_name("local-search-steps")
+                .long("local-search-steps")
+                .help("Maximum number of improving swaps per local search")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::with_name("local-search-prob")
+                .long("local-search-prob")
+                .help("The probability that the offspring is refined by the local search")
+                .default_value("1.0"),
+        )
+        .arg(
+            Arg::with_name("learning")
+                .long("learning")
+                .help("Whether the refined array replaces the offspring [lamarckian, baldwinian]")
+                .default_value("lamarckian"),
+        )
+        .arg(
+            Arg::with_name("es")
+                .long("es")
+                .help("Replace the GA with an evolution strategy [none, 1+1, 1+lambda, mu+lambda]; mu is the population size")
+                .default_value("none"),
+        )
+        .arg(
+            Arg::with_name("lambda")
+                .long("lambda")
+                .help("Offspring per generation of the evolution strategy")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::with_name("es-strength")
+                .long("es-strength")
+                .help("Swaps per offspring of the evolution strategy: n, uniform:n, poisson:lambda or power:beta (fast GA)")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("eda")
+                .long("eda")
+                .help("Replace the GA with an estimation-of-distribution algorithm sampling pop-size balanced arrays per epoch [none, pbil, umda]")
+                .default_value("none"),
+        )
+        .arg(
+            Arg::with_name("eda-learning-rate")
+                .long("eda-learning-rate")
+                .help("Learning rate of the PBIL model (UMDA always uses 1)")
+                .default_value("0.1"),
+        )
+        .arg(
+            Arg::with_name("eda-elite")
+                .long("eda-elite")
+                .help("Number of best samples used to update the EDA model")
+                .default_value("5"),
+        )
         .get_matches();
 
     let ngrande = get_arg!(matches, "N", usize);
 
-    let f = match matches.value_of("fitness").unwrap() {
-        "Delta" => oarray::FitnessFunction::Delta,
-        "DeltaFast" => oarray::FitnessFunction::DeltaFast,
-        "Cidev" => oarray::FitnessFunction::Cidev,
-        "SheerLuck" => oarray::FitnessFunction::SheerLuck,
-        "Walsh" => oarray::FitnessFunction::Walsh(get_arg!(matches, "fitness-exp", u32)),
-        "WalshFast" => oarray::FitnessFunction::WalshFaster(get_arg!(matches, "fitness-exp", u32)),
-        "WalshRec" => oarray::FitnessFunction::WalshRec(get_arg!(matches, "fitness-exp", u32)),
-        _ => panic!("Invalid function name"),
+    let f = oarray::fitness_by_name(
+        matches.value_of("fitness").unwrap(),
+        get_arg!(matches, "fitness-exp", u32),
+    )
+    .expect("Invalid function name");
+    let memetic = match matches.value_of("local-search").unwrap() {
+        "none" => None,
+        _ => Some(memetic::Memetic {
+            strategy: get_arg!(matches, "local-search", LocalSearch),
+            steps: get_arg!(matches, "local-search-steps", usize),
+            probability: get_arg!(matches, "local-search-prob", f64),
+            learning: get_arg!(matches, "learning", Learning),
+        }),
     };
+    let es = es::EvolutionStrategy::from_name(
+        matches.value_of("es").unwrap(),
+        get_arg!(matches, "pop-size", usize),
+        get_arg!(matches, "lambda", usize),
+        get_arg!(matches, "es-strength", SwapCount),
+    )
+    .expect("Invalid evolution strategy");
+    let eda = eda::Eda::from_name(
+        matches.value_of("eda").unwrap(),
+        get_arg!(matches, "eda-learning-rate", f64),
+        get_arg!(matches, "eda-elite", usize),
+    )
+    .expect("Invalid EDA");
     let params = run::RunParameters {
         ngrande,
         k: get_arg!(matches, "k", usize),
@@ -126,9 +335,43 @@ fn main() {
         epochs: get_arg!(matches, "epochs", usize),
         pop_size: get_arg!(matches, "pop-size", usize),
         mutation_prob: get_arg!(matches, "mutation-prob", f64),
-        breed_factor: get_arg!(matches, "breed-factor", f64),
-        survival_factor: get_arg!(matches, "survival-factor", f64),
+        epoch: ConfigurableEpoch::from_names(
+            matches.value_of("selection").unwrap(),
+            matches.value_of("replacement").unwrap(),
+            get_arg!(matches, "tournament-size", usize),
+            get_arg!(matches, "breed-factor", f64),
+            get_arg!(matches, "survival-factor", f64),
+            get_arg!(matches, "offspring", usize),
+        )
+        .expect("Invalid epoch configuration"),
+        crossover: get_arg!(matches, "crossover", BalancedCrossover),
+        mutation: BalancedMutation::from_name(
+            matches.value_of("mutation").unwrap(),
+            get_arg!(matches, "swap-count", SwapCount),
+        )
+        .expect("Invalid mutation"),
+        rate_adaptation: get_arg!(matches, "rate-adaptation", RateAdaptation),
         fitness_f: f,
+        multi_objective: get_arg!(matches, "multi-objective", bool),
+        mo_degree: get_arg!(matches, "mo-degree", bool),
+        cache_size: get_arg!(matches, "cache-size", usize),
+        islands: IslandParameters {
+            islands: get_arg!(matches, "islands", usize),
+            topology: get_arg!(matches, "topology", Topology),
+            migration_interval: get_arg!(matches, "migration-interval", usize),
+            migration_size: get_arg!(matches, "migration-size", usize),
+        },
+        stagnation: StagnationParameters::from_names(
+            get_arg!(matches, "stagnation", usize),
+            matches.value_of("restart").unwrap(),
+            get_arg!(matches, "restart-fraction", f64),
+            get_arg!(matches, "elite", usize),
+            get_arg!(matches, "diversity-every", usize),
+        )
+        .expect("Invalid restart policy"),
+        memetic,
+        es,
+        eda,
     };
     let runs = get_arg!(matches, "runs", usize);
     let threads = get_arg!(matches, "threads", usize);
@@ -153,6 +396,19 @@ fn main() {
         "Looking for OA[N: {}, k: {}, s: 2, t: {}]",
         params.ngrande, params.k, params.t
     );
+    if let Some(violation) = oarray::bounds::check_parameters(params.ngrande, params.k, params.t) {
+        warn!(
+            "OA[N: {}, k: {}, s: 2, t: {}] cannot exist: {}",
+            params.ngrande, params.k, params.t, violation
+        );
+    }
+    if params.multi_objective && params.mo_degree && params.k > nsga2::MAX_DEGREE_K {
+        warn!(
+            "k = {} is too large to compute the algebraic degree (max {}): ignoring --mo-degree",
+            params.k,
+            nsga2::MAX_DEGREE_K
+        );
+    }
     debug!("{:#?}", params);
 
     let show_progress = threads == 1;
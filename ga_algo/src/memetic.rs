@@ -0,0 +1,105 @@
+//! Algoritmo memetico: ogni figlio può essere raffinato da una ricerca locale.
+
+use genetic_operators::GAOArray;
+use oarray::LocalSearch;
+use rand::Rng;
+use spiril::unit::Unit;
+use std::str::FromStr;
+
+/// Cosa resta della ricerca locale nel figlio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Learning {
+    /// il genotipo è sostituito da quello migliorato
+    Lamarckian,
+    /// il genotipo resta invariato, ma la fitness è quella dopo la ricerca
+    Baldwinian,
+}
+
+impl FromStr for Learning {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lamarckian" => Ok(Learning::Lamarckian),
+            "baldwinian" => Ok(Learning::Baldwinian),
+            _ => Err(format!("Unknown learning {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Memetic {
+    pub strategy: LocalSearch,
+    /// massimo numero di scambi per ricerca
+    pub steps: usize,
+    /// probabilità che un figlio sia raffinato
+    pub probability: f64,
+    pub learning: Learning,
+}
+
+impl GAOArray {
+    /// Con probabilità `memetic.probability` applica la ricerca locale.
+    /// I vicini sono valutati dalla ricerca con lo spettro di Walsh e non
+    /// passano dalla cache: la fitness si calcola solo sul risultato, che è
+    /// tenuto se non peggiora quella del figlio.
+    pub(crate) fn refine(&mut self, memetic: &Memetic, rng: &mut impl Rng) {
+        if rng.gen_range::<f64>(0.0, 1.0) >= memetic.probability {
+            return;
+        }
+        let mut improved = self.oa.clone();
+        match improved.local_search(memetic.strategy, memetic.steps, rng) {
+            Some(res) if res.steps > 0 => {}
+            _ => return,
+        }
+        let fitness = improved.fitness();
+        if fitness < self.fitness() {
+            return;
+        }
+        //un OA trovato dalla ricerca locale non va perso
+        if memetic.learning == Learning::Lamarckian || -fitness < f64::EPSILON {
+            self.cache.get_or_insert_with(&improved.d, || fitness);
+            self.oa = improved;
+        } else {
+            self.learned_fitness = Some(fitness);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use genetic_operators::test_unit;
+    use oarray::FitnessCache;
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_refine() {
+        let mut r = thread_rng();
+        for &learning in &[Learning::Lamarckian, Learning::Baldwinian] {
+            let memetic = Memetic {
+                strategy: LocalSearch::Steepest,
+                steps: 2,
+                probability: 1.0,
+                learning,
+            };
+            for _ in 0..10 {
+                let mut a = GAOArray {
+                    cache: Arc::new(FitnessCache::new(100)),
+                    memetic: Some(memetic),
+                    ..test_unit(8, 4, 2, &mut r)
+                };
+                let before = a.clone();
+                a.refine(&memetic, &mut r);
+                assert!(a.fitness() >= before.fitness());
+                if learning == Learning::Baldwinian && a.learned_fitness.is_some() {
+                    assert!(a.oa == before.oa);
+                } else {
+                    assert!(a.learned_fitness.is_none());
+                    assert!(a.fitness() == a.oa.fitness());
+                }
+            }
+        }
+        assert!("baldwinian".parse::<Learning>() == Ok(Learning::Baldwinian));
+        assert!("darwinian".parse::<Learning>().is_err());
+    }
+}
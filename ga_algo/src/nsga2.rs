@@ -0,0 +1,273 @@
+//! Ricerca multi-obiettivo in stile NSGA-II: ottimizza contemporaneamente
+//! la fitness dell'array (difetto di ortogonalità), la nonlinearità
+//! della funzione indicatrice e, opzionalmente, il suo grado algebrico.
+//! Tutti gli obiettivi vanno massimizzati.
+
+use ga_common::Mutator;
+use genetic_operators::GAOArray;
+use oarray::{FitnessCache, OArray};
+use pbr::ProgressBar;
+use rand::{thread_rng, Rng};
+use run::RunParameters;
+use spiril::unit::Unit;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::f64;
+use std::sync::Arc;
+
+/// Oltre questo k la tabella di verità (2^k celle) per il grado algebrico
+/// costa troppo per calcolarla a ogni individuo
+pub(crate) const MAX_DEGREE_K: usize = 20;
+
+struct Individual {
+    unit: GAOArray,
+    objectives: Vec<f64>,
+    rank: usize,
+    crowding: f64,
+}
+
+impl Individual {
+    fn new(unit: GAOArray, with_degree: bool) -> Self {
+        let objectives = objectives(&unit, with_degree);
+        Individual {
+            unit,
+            objectives,
+            rank: 0,
+            crowding: 0.0,
+        }
+    }
+}
+
+/// Calcola gli obiettivi: [fitness, nonlinearità, (grado algebrico)].
+/// La fitness è quella dell'unità, quindi passa dalla cache e tiene conto
+/// della fitness appresa dalla ricerca locale.
+pub(crate) fn objectives(unit: &GAOArray, with_degree: bool) -> Vec<f64> {
+    let mut out = vec![unit.fitness(), unit.oa.walsh_tform().nonlinearity()];
+    if with_degree {
+        out.push(unit.oa.truth_table().algebraic_degree() as f64);
+    }
+    out
+}
+
+/// `a` domina `b` se non è peggiore in nessun obiettivo ed è migliore in almeno uno
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b.iter()).all(|(x, y)| x >= y) && a.iter().zip(b.iter()).any(|(x, y)| x > y)
+}
+
+/// Ordinamento non dominato veloce: restituisce i fronti (indici in `objs`),
+/// dal primo (non dominato) all'ultimo
+fn non_dominated_sort(objs: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    let n = objs.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut domination_count = vec![0usize; n];
+    let mut fronts = vec![Vec::new()];
+    for p in 0..n {
+        for q in 0..n {
+            if dominates(&objs[p], &objs[q]) {
+                dominated_by[p].push(q);
+            } else if dominates(&objs[q], &objs[p]) {
+                domination_count[p] += 1;
+            }
+        }
+        if domination_count[p] == 0 {
+            fronts[0].push(p);
+        }
+    }
+    let mut i = 0;
+    while !fronts[i].is_empty() {
+        let mut next = Vec::new();
+        for &p in &fronts[i] {
+            for &q in &dominated_by[p] {
+                domination_count[q] -= 1;
+                if domination_count[q] == 0 {
+                    next.push(q);
+                }
+            }
+        }
+        i += 1;
+        fronts.push(next);
+    }
+    fronts.pop();
+    fronts
+}
+
+/// Distanza di affollamento degli elementi di `front`, nello stesso ordine
+fn crowding_distance(objs: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    let mut dist = vec![0.0; front.len()];
+    if front.is_empty() {
+        return dist;
+    }
+    let n_obj = objs[front[0]].len();
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    for m in 0..n_obj {
+        order.sort_by(|&a, &b| {
+            objs[front[a]][m]
+                .partial_cmp(&objs[front[b]][m])
+                .unwrap_or(Ordering::Equal)
+        });
+        let min = objs[front[order[0]]][m];
+        let max = objs[front[order[front.len() - 1]]][m];
+        dist[order[0]] = f64::INFINITY;
+        dist[order[front.len() - 1]] = f64::INFINITY;
+        if max - min < f64::EPSILON {
+            continue;
+        }
+        for w in 1..front.len().saturating_sub(1) {
+            let prev = objs[front[order[w - 1]]][m];
+            let next = objs[front[order[w + 1]]][m];
+            dist[order[w]] += (next - prev) / (max - min);
+        }
+    }
+    dist
+}
+
+/// Assegna rango e distanza di affollamento a tutta la popolazione
+fn assign_rank_crowding(pop: &mut [Individual]) -> Vec<Vec<usize>> {
+    let objs: Vec<Vec<f64>> = pop.iter().map(|i| i.objectives.clone()).collect();
+    let fronts = non_dominated_sort(&objs);
+    for (rank, front) in fronts.iter().enumerate() {
+        let dist = crowding_distance(&objs, front);
+        for (&i, d) in front.iter().zip(dist) {
+            pop[i].rank = rank;
+            pop[i].crowding = d;
+        }
+    }
+    fronts
+}
+
+/// Confronto per affollamento: `Greater` se `a` è migliore di `b`
+fn crowded_cmp(a: &Individual, b: &Individual) -> Ordering {
+    b.rank.cmp(&a.rank).then(
+        a.crowding
+            .partial_cmp(&b.crowding)
+            .unwrap_or(Ordering::Equal),
+    )
+}
+
+fn tournament<'a>(pop: &'a [Individual], rng: &mut impl Rng) -> &'a Individual {
+    let a = &pop[rng.gen_range(0, pop.len())];
+    let b = &pop[rng.gen_range(0, pop.len())];
+    if crowded_cmp(a, b) == Ordering::Less {
+        b
+    } else {
+        a
+    }
+}
+
+pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
+    let mut rng = thread_rng();
+    let cache = Arc::new(FitnessCache::new(p.cache_size));
+    let mutator = Mutator::new(p.mutation, p.rate_adaptation, p.mutation_prob);
+    let with_degree = p.mo_degree && p.k <= MAX_DEGREE_K;
+    let mut pop: Vec<Individual> = (0..p.pop_size)
+        .map(|_| {
+            let unit = GAOArray {
+                oa: OArray::new_random_balanced(p.ngrande, p.k, p.t, &mut rng, p.fitness_f),
+                mutation_prob: p.mutation_prob,
+                crossover: p.crossover,
+                mutator: mutator.clone(),
+                cache: cache.clone(),
+                memetic: p.memetic,
+                learned_fitness: None,
+            };
+            Individual::new(unit, with_degree)
+        })
+        .collect();
+    assign_rank_crowding(&mut pop);
+
+    let mut pbar = if show_progress {
+        Some(ProgressBar::new(p.epochs as u64))
+    } else {
+        None
+    };
+    for _ in 0..p.epochs {
+        let offspring: Vec<Individual> = (0..p.pop_size)
+            .map(|_| {
+                let a = tournament(&pop, &mut rng);
+                let b = tournament(&pop, &mut rng);
+                Individual::new(a.unit.breed_with(&b.unit), with_degree)
+            })
+            .collect();
+        pop.extend(offspring);
+        let fronts = assign_rank_crowding(&mut pop);
+
+        //selezione: fronti interi finché possibile, poi per affollamento
+        let mut keep = vec![false; pop.len()];
+        let mut kept = 0;
+        for front in fronts {
+            if kept + front.len() <= p.pop_size {
+                for &i in &front {
+                    keep[i] = true;
+                }
+                kept += front.len();
+            } else {
+                let mut front = front;
+                front.sort_by(|&a, &b| crowded_cmp(&pop[b], &pop[a]));
+                for &i in front.iter().take(p.pop_size - kept) {
+                    keep[i] = true;
+                }
+                break;
+            }
+        }
+        let mut flags = keep.iter();
+        pop.retain(|_| *flags.next().unwrap());
+
+        if let Some(pbar) = pbar.as_mut() {
+            let best = pop
+                .iter()
+                .map(|i| i.objectives[0])
+                .fold(f64::NEG_INFINITY, f64::max);
+            let front_size = pop.iter().filter(|i| i.rank == 0).count();
+            pbar.message(&format!(" Best: {:.4}, Front: {}; iteration ", best, front_size));
+            pbar.inc();
+        }
+    }
+
+    let mut front: Vec<&Individual> = pop.iter().filter(|i| i.rank == 0).collect();
+    front.sort_by(|a, b| b.objectives.partial_cmp(&a.objectives).unwrap_or(Ordering::Equal));
+    //array uguali possono non essere adiacenti se altri hanno gli stessi obiettivi
+    let mut seen = HashSet::new();
+    front.retain(|i| seen.insert(&i.unit.oa.d));
+    //il fronte è il risultato della run: una sola scrittura, così i
+    //fronti di thread diversi non si mescolano
+    let mut out = format!("Pareto front: {} arrays\n", front.len());
+    for i in &front {
+        out += &format!("Objectives: {:?}\n{}\n", i.objectives, i.unit.oa);
+    }
+    print!("{}", out);
+    let found: Vec<&&Individual> = front
+        .iter()
+        .filter(|i| -i.objectives[0] < f64::EPSILON)
+        .collect();
+    if found.is_empty() {
+        (false, false)
+    } else {
+        (true, found.iter().any(|i| i.unit.oa.check_linear()))
+    }
+}
+
+#[test]
+fn test_non_dominated_sort() {
+    let objs = vec![
+        vec![0.0, 1.0],
+        vec![-1.0, 2.0],
+        vec![-1.0, 1.0],
+        vec![-2.0, 0.0],
+        vec![0.0, 1.0],
+    ];
+    let fronts = non_dominated_sort(&objs);
+    assert!(fronts == vec![vec![0, 1, 4], vec![2], vec![3]]);
+    assert!(dominates(&objs[0], &objs[2]));
+    assert!(!dominates(&objs[0], &objs[4]));
+    assert!(!dominates(&objs[0], &objs[1]));
+}
+
+#[test]
+fn test_crowding_distance() {
+    let objs = vec![vec![0.0, 4.0], vec![1.0, 3.0], vec![2.0, 1.0], vec![4.0, 0.0]];
+    let front = vec![0, 1, 2, 3];
+    let dist = crowding_distance(&objs, &front);
+    assert!(dist[0].is_infinite() && dist[3].is_infinite());
+    assert!((dist[1] - (2.0 / 4.0 + 3.0 / 4.0)).abs() < 1e-9);
+    assert!((dist[2] - (3.0 / 4.0 + 3.0 / 4.0)).abs() < 1e-9);
+}
@@ -1,11 +1,18 @@
-use spiril::{population::Population, unit::Unit, epoch::DefaultEpoch};
+use spiril::{population::Population, unit::Unit};
 use std::f64;
+use std::sync::Arc;
 
-//mod epoch;
-//use epoch::TournamentEpoch;
 
+use ga_common::{
+    run_islands, BalancedCrossover, BalancedMutation, ConfigurableEpoch, Diversity,
+    IslandParameters, Mutator, RateAdaptation, RestartEpoch, StagnationParameters,
+};
 use genetic_operators::GAOArray;
-use oarray::{OArray, FitnessFunction};
+use eda::{self, Eda};
+use es::{self, EvolutionStrategy};
+use memetic::Memetic;
+use nsga2;
+use oarray::{FitnessCache, FitnessFunction, OArray};
 use rand::thread_rng;
 use pbr::ProgressBar;
 
@@ -16,43 +23,82 @@ pub(crate) struct RunParameters {
     pub t: u32,
     pub pop_size: usize,
     pub mutation_prob: f64,
-    pub breed_factor: f64,
-    pub survival_factor: f64,
+    pub epoch: ConfigurableEpoch,
+    pub crossover: BalancedCrossover,
+    pub mutation: BalancedMutation,
+    pub rate_adaptation: RateAdaptation,
     pub epochs: usize,
-    pub fitness_f: FitnessFunction
+    pub fitness_f: FitnessFunction,
+    pub multi_objective: bool,
+    pub mo_degree: bool,
+    pub cache_size: usize,
+    pub islands: IslandParameters,
+    pub stagnation: StagnationParameters,
+    pub memetic: Option<Memetic>,
+    pub es: Option<EvolutionStrategy>,
+    pub eda: Option<Eda>,
 }
 
 pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
-    let mut rng = thread_rng();
+    if p.multi_objective {
+        return nsga2::run(p, show_progress);
+    }
+    if let Some(strategy) = p.es {
+        return es::run(p, &strategy, show_progress);
+    }
+    if let Some(eda) = p.eda {
+        return eda::run(p, &eda, show_progress);
+    }
     let ngrande = p.ngrande;
-    let units: Vec<GAOArray> = (0..p.pop_size)
-        .map(|_i| GAOArray {
-            oa: OArray::new_random_balanced(ngrande, p.k, p.t, &mut rng, p.fitness_f),
-            mutation_prob: p.mutation_prob,
-        })
-        .collect();
+    let cache = Arc::new(FitnessCache::new(p.cache_size));
+    let mutator = Mutator::new(p.mutation, p.rate_adaptation, p.mutation_prob);
+    let n_islands = p.islands.islands.max(1);
+    let new_unit = || GAOArray {
+        oa: OArray::new_random_balanced(ngrande, p.k, p.t, &mut thread_rng(), p.fitness_f),
+        mutation_prob: p.mutation_prob,
+        crossover: p.crossover,
+        mutator: mutator.clone(),
+        cache: cache.clone(),
+        memetic: p.memetic,
+        learned_fitness: None,
+    };
+    let mut units: Vec<GAOArray> = (0..p.pop_size * n_islands).map(|_i| new_unit()).collect();
 
     let mut pbar = ProgressBar::new(p.epochs as u64);
 
-    //let epoch = TournamentEpoch::new();
-    let epoch = DefaultEpoch::new(p.breed_factor, p.survival_factor);
-    let f = Population::new(units)
-        .set_size(p.pop_size)
-        .register_callback(Box::new(move |i, j| {
+    let epoch = RestartEpoch::new(p.epoch, p.stagnation, new_unit);
+    let f = if n_islands > 1 {
+        let populations: Vec<Vec<GAOArray>> = (0..n_islands)
+            .map(|_| units.split_off(units.len() - p.pop_size))
+            .collect();
+        run_islands(&p.islands, p.epochs, p.pop_size, &epoch, populations, move |i, j| {
             if show_progress {
                 pbar.message(&format!(" Best: {:.4}, Mean: {:.4}; iteration ", i, j));
                 (&mut pbar).inc();
             }
-            if -i < f64::EPSILON {
-                return false;
-            }
-            true
-        }))
-        .epochs(p.epochs as u32, &epoch)
-        .finish();
+        })
+    } else {
+        Population::new(units)
+            .set_size(p.pop_size)
+            .register_callback(Box::new(move |i, j| {
+                if show_progress {
+                    pbar.message(&format!(" Best: {:.4}, Mean: {:.4}; iteration ", i, j));
+                    (&mut pbar).inc();
+                }
+                if -i < f64::EPSILON {
+                    return false;
+                }
+                true
+            }))
+            .epochs(p.epochs as u32, &epoch)
+            .finish()
+    };
     let asd = f
         .iter()
         .max_by(|&a, &b| a.fitness().partial_cmp(&b.fitness()).unwrap());
+    debug!("Fitness cache: {}", cache);
+    let final_units: Vec<&GAOArray> = f.iter().collect();
+    debug!("Final population: {}", Diversity::measure(&final_units));
     if -asd.unwrap().fitness() < f64::EPSILON {
         debug!("{}", asd.unwrap().oa);
         (true, asd.unwrap().oa.check_linear())
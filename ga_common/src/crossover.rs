@@ -0,0 +1,256 @@
+//! Crossover che preservano il bilanciamento delle colonne: se ogni colonna
+//! dei genitori ha tanti 0 quanti 1, lo stesso vale per il figlio.
+
+use rand::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalancedCrossover {
+    /// ogni cella da un genitore a caso, in ordine casuale, con contatori
+    Uniform,
+    /// prefisso dal primo genitore e suffisso dal secondo, con contatori
+    OnePoint,
+    /// segmento centrale dal secondo genitore, con contatori
+    TwoPoint,
+    /// tiene gli 1 comuni e sceglie a caso i restanti tra quelli non comuni
+    MapOfOnes,
+    /// ogni colonna intera da un genitore a caso
+    ColumnExchange,
+    /// un blocco di righe dal secondo genitore, poi riparazione del bilanciamento
+    RowBlock,
+}
+
+impl FromStr for BalancedCrossover {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(BalancedCrossover::Uniform),
+            "one-point" => Ok(BalancedCrossover::OnePoint),
+            "two-point" => Ok(BalancedCrossover::TwoPoint),
+            "map-of-ones" => Ok(BalancedCrossover::MapOfOnes),
+            "column-exchange" => Ok(BalancedCrossover::ColumnExchange),
+            "row-block" => Ok(BalancedCrossover::RowBlock),
+            _ => Err(format!("Unknown crossover {}", s)),
+        }
+    }
+}
+
+impl BalancedCrossover {
+    /// Unisce `a` e `b`, memorizzati per colonne di `ngrande` celle, in `out`
+    pub fn apply(self, a: &[bool], b: &[bool], out: &mut [bool], ngrande: usize, r: &mut impl Rng) {
+        assert!(a.len() == b.len() && a.len() == out.len());
+        let cols = a
+            .chunks(ngrande)
+            .zip(b.chunks(ngrande))
+            .zip(out.chunks_mut(ngrande));
+        match self {
+            BalancedCrossover::RowBlock => {
+                let start = r.gen_range(0, ngrande);
+                let end = r.gen_range(start, ngrande) + 1;
+                for ((a, b), out) in cols {
+                    row_block_crossover(a, b, out, start, end, r);
+                }
+            }
+            _ => {
+                for ((a, b), out) in cols {
+                    self.apply_col(a, b, out, r);
+                }
+            }
+        }
+    }
+
+    /// Unisce due colonne bilanciate
+    pub fn apply_col(self, a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
+        let ngrande = a.len();
+        match self {
+            BalancedCrossover::Uniform => balanced_crossover(a, b, out, r),
+            BalancedCrossover::OnePoint => {
+                let cut = r.gen_range(0, ngrande + 1);
+                counter_crossover(a, b, out, cut, ngrande);
+            }
+            BalancedCrossover::TwoPoint => {
+                let start = r.gen_range(0, ngrande + 1);
+                let end = r.gen_range(start, ngrande + 1);
+                counter_crossover(a, b, out, start, end);
+            }
+            BalancedCrossover::MapOfOnes => map_of_ones_crossover(a, b, out, r),
+            BalancedCrossover::ColumnExchange => {
+                out.copy_from_slice(if r.gen() { a } else { b });
+            }
+            BalancedCrossover::RowBlock => {
+                let start = r.gen_range(0, ngrande);
+                let end = r.gen_range(start, ngrande) + 1;
+                row_block_crossover(a, b, out, start, end, r);
+            }
+        }
+    }
+}
+
+/// Unisce due OArray in in modo che il risultato sia bilanciato
+pub fn balanced_crossover(a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
+    let ngrande = a.len();
+    let balance = ngrande / 2;
+    let mut pos: Vec<_> = (0..ngrande).collect();
+    r.shuffle(&mut pos);
+    let mut cnt = [balance, balance];
+    for j in pos {
+        let choice = if cnt[0] == 0 {
+            true
+        } else if cnt[1] == 0 {
+            false
+        } else {
+            *r.choose(&[a[j], b[j]]).unwrap()
+        };
+
+        out[j] = choice;
+        cnt[choice as usize] -= 1;
+    }
+}
+
+/// Copia `b` nelle posizioni `start..end` e `a` nelle altre, scorrendo in
+/// ordine: quando un simbolo ha raggiunto N/2 occorrenze il resto è
+/// riempito con l'altro
+fn counter_crossover(a: &[bool], b: &[bool], out: &mut [bool], start: usize, end: usize) {
+    let balance = a.len() / 2;
+    let mut cnt = [balance, balance];
+    for j in 0..a.len() {
+        let proposal = if j >= start && j < end { b[j] } else { a[j] };
+        let choice = if cnt[proposal as usize] == 0 { !proposal } else { proposal };
+        out[j] = choice;
+        cnt[choice as usize] -= 1;
+    }
+}
+
+/// Gli 1 presenti in entrambi i genitori restano; i rimanenti sono scelti
+/// a caso tra le posizioni in cui solo un genitore ha un 1. Con genitori
+/// non bilanciati (ad esempio dopo gli scambi di righe di ga_hill) gli 1
+/// comuni possono essere troppi o i candidati troppo pochi: il bilanciamento
+/// viene ripristinato alla fine
+fn map_of_ones_crossover(a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
+    let balance = a.len() / 2;
+    let mut candidates = Vec::new();
+    let mut ones = 0;
+    for j in 0..a.len() {
+        out[j] = a[j] && b[j];
+        if out[j] {
+            ones += 1;
+        } else if a[j] || b[j] {
+            candidates.push(j);
+        }
+    }
+    r.shuffle(&mut candidates);
+    for &j in candidates.iter().take(balance.saturating_sub(ones)) {
+        out[j] = true;
+    }
+    repair_balance(out, r);
+}
+
+/// Prende le righe `start..end` da `b` e le altre da `a`, poi ripristina
+/// il bilanciamento invertendo celle a caso del simbolo in eccesso
+fn row_block_crossover(
+    a: &[bool],
+    b: &[bool],
+    out: &mut [bool],
+    start: usize,
+    end: usize,
+    r: &mut impl Rng,
+) {
+    for j in 0..a.len() {
+        out[j] = if j >= start && j < end { b[j] } else { a[j] };
+    }
+    repair_balance(out, r);
+}
+
+/// Inverte celle scelte a caso tra quelle del simbolo in eccesso finché
+/// la colonna non è bilanciata
+pub fn repair_balance(col: &mut [bool], r: &mut impl Rng) {
+    let ones = col.iter().filter(|&&x| x).count();
+    let balance = col.len() / 2;
+    let (surplus, excess) = if ones > balance {
+        (true, ones - balance)
+    } else {
+        (false, balance - ones)
+    };
+    let mut pos: Vec<usize> = (0..col.len()).filter(|&j| col[j] == surplus).collect();
+    r.shuffle(&mut pos);
+    for &j in pos.iter().take(excess) {
+        col[j] = !surplus;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::thread_rng;
+
+    fn random_balanced(n: usize, r: &mut impl Rng) -> Vec<bool> {
+        let mut v: Vec<bool> = (0..n).map(|i| i % 2 == 0).collect();
+        r.shuffle(&mut v);
+        v
+    }
+    fn is_balanced(v: &[bool]) -> bool {
+        2 * v.iter().filter(|&&x| x).count() == v.len()
+    }
+
+    #[test]
+    fn test_balanced() {
+        let mut r = thread_rng();
+        let ops = [
+            "uniform",
+            "one-point",
+            "two-point",
+            "map-of-ones",
+            "column-exchange",
+            "row-block",
+        ];
+        for op in &ops {
+            let op: BalancedCrossover = op.parse().unwrap();
+            for _ in 0..200 {
+                let a: Vec<bool> = (0..4).flat_map(|_| random_balanced(16, &mut r)).collect();
+                let b: Vec<bool> = (0..4).flat_map(|_| random_balanced(16, &mut r)).collect();
+                let mut out = vec![false; 64];
+                op.apply(&a, &b, &mut out, 16, &mut r);
+                assert!(out.chunks(16).all(is_balanced), "{:?}", op);
+                //i valori comuni ai genitori si conservano
+                if op == BalancedCrossover::MapOfOnes {
+                    for j in 0..64 {
+                        assert!(a[j] != b[j] || out[j] == a[j]);
+                    }
+                }
+                if op == BalancedCrossover::ColumnExchange {
+                    for ((a, b), out) in a.chunks(16).zip(b.chunks(16)).zip(out.chunks(16)) {
+                        assert!(out == a || out == b);
+                    }
+                }
+            }
+        }
+        assert!("three-point".parse::<BalancedCrossover>().is_err());
+    }
+
+    #[test]
+    fn test_unbalanced_parents() {
+        //più di N/2 uni comuni: il figlio va comunque bilanciato
+        let mut r = thread_rng();
+        let a = [true, true, true, true, true, false, false, false];
+        let b = [true, true, true, true, true, false, true, false];
+        let mut out = [false; 8];
+        for _ in 0..20 {
+            map_of_ones_crossover(&a, &b, &mut out, &mut r);
+            assert!(is_balanced(&out));
+            assert!(!out[5] && !out[7]);
+        }
+    }
+
+    #[test]
+    fn test_counter_crossover() {
+        let a = [true, true, false, false, true, false];
+        let b = [false, false, true, true, true, false];
+        let mut out = [false; 6];
+        counter_crossover(&a, &b, &mut out, 6, 6);
+        assert!(out == a);
+        counter_crossover(&a, &b, &mut out, 2, 6);
+        assert!(out == [true, true, true, false, false, false]);
+        counter_crossover(&a, &b, &mut out, 0, 2);
+        assert!(out == [false, false, false, true, true, true]);
+    }
+}
@@ -0,0 +1,300 @@
+//! Misure di diversità della popolazione e riavvio in caso di stagnazione.
+
+use rand::Rng;
+use spiril::{epoch::Epoch, population::LazyUnit, unit::Unit};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::f64;
+use std::fmt::{Display, Error, Formatter};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+/// Unità rappresentate da una stringa di bit, memorizzata per colonne
+pub trait Genotype {
+    fn genotype(&self) -> &[bool];
+    /// lunghezza di una colonna
+    fn col_len(&self) -> usize;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diversity {
+    /// distanza di Hamming media tra due genotipi distinti della popolazione
+    pub mean_hamming: f64,
+    /// numero di genotipi diversi
+    pub distinct: usize,
+    /// entropia media delle celle di ogni colonna, tra 0 e 1
+    pub column_entropy: Vec<f64>,
+}
+
+fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        0.0
+    } else {
+        -p * p.log2() - (1.0 - p) * (1.0 - p).log2()
+    }
+}
+
+impl Diversity {
+    pub fn measure<T: Genotype>(units: &[&T]) -> Self {
+        let n = units.len();
+        if n == 0 {
+            return Diversity {
+                mean_hamming: 0.0,
+                distinct: 0,
+                column_entropy: vec![],
+            };
+        }
+        let genotypes: Vec<&[bool]> = units.iter().map(|u| u.genotype()).collect();
+        let len = genotypes[0].len();
+        let col_len = units[0].col_len().max(1);
+
+        let mut tot_hamming = 0usize;
+        for i in 0..n {
+            for j in i + 1..n {
+                tot_hamming += genotypes[i]
+                    .iter()
+                    .zip(genotypes[j].iter())
+                    .filter(|(a, b)| a != b)
+                    .count();
+            }
+        }
+        let pairs = n * (n - 1) / 2;
+        let mean_hamming = if pairs == 0 {
+            0.0
+        } else {
+            tot_hamming as f64 / pairs as f64
+        };
+
+        let distinct = genotypes.iter().collect::<HashSet<_>>().len();
+
+        let mut ones = vec![0usize; len];
+        for g in &genotypes {
+            for (cnt, &x) in ones.iter_mut().zip(g.iter()) {
+                *cnt += x as usize;
+            }
+        }
+        let column_entropy = ones
+            .chunks(col_len)
+            .map(|col| {
+                col.iter()
+                    .map(|&c| binary_entropy(c as f64 / n as f64))
+                    .sum::<f64>()
+                    / col.len() as f64
+            })
+            .collect();
+        Diversity {
+            mean_hamming,
+            distinct,
+            column_entropy,
+        }
+    }
+}
+
+impl Display for Diversity {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let cols = self.column_entropy.len().max(1) as f64;
+        let mean = self.column_entropy.iter().sum::<f64>() / cols;
+        let min = self.column_entropy.iter().cloned().fold(f64::INFINITY, f64::min);
+        write!(
+            f,
+            "Hamming: {:.2}, distinct: {}, entropy: {:.3} (min column {:.3})",
+            self.mean_hamming,
+            self.distinct,
+            mean,
+            if min.is_finite() { min } else { 0.0 }
+        )
+    }
+}
+
+/// Cosa fare quando la popolazione stagna
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// reinizializza la frazione data dei peggiori
+    Partial(f64),
+    /// reinizializza tutti tranne l'élite
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StagnationParameters {
+    /// epoche senza miglioramenti prima del riavvio; 0 per non riavviare mai
+    pub patience: usize,
+    pub policy: RestartPolicy,
+    /// individui migliori che sopravvivono al riavvio
+    pub elite: usize,
+    /// ogni quante epoche registrare la diversità nel log; 0 per mai
+    pub diversity_every: usize,
+}
+
+impl StagnationParameters {
+    /// Costruisce i parametri dai valori usati sulla linea di comando
+    pub fn from_names(
+        patience: usize,
+        restart: &str,
+        restart_fraction: f64,
+        elite: usize,
+        diversity_every: usize,
+    ) -> Result<Self, String> {
+        let policy = match restart {
+            "partial" => RestartPolicy::Partial(restart_fraction),
+            "full" => RestartPolicy::Full,
+            _ => return Err(format!("Unknown restart policy {}", restart)),
+        };
+        Ok(StagnationParameters {
+            patience,
+            policy,
+            elite,
+            diversity_every,
+        })
+    }
+}
+
+#[derive(Default)]
+struct StagnationState {
+    epoch: usize,
+    best: Option<f64>,
+    since_improvement: usize,
+    restarts: usize,
+}
+
+/// Epoca che, dopo quella interna, misura la diversità e riavvia la
+/// popolazione se la miglior fitness non migliora da `patience` epoche.
+/// Lo stato è separato per thread, in modo che ogni isola abbia il suo.
+pub struct RestartEpoch<E, F> {
+    inner: E,
+    params: StagnationParameters,
+    new_unit: F,
+    state: Mutex<HashMap<ThreadId, StagnationState>>,
+}
+
+impl<E, F> RestartEpoch<E, F> {
+    /// `new_unit` genera un individuo casuale per il riavvio
+    pub fn new(inner: E, params: StagnationParameters, new_unit: F) -> Self {
+        RestartEpoch {
+            inner,
+            params,
+            new_unit,
+            state: Mutex::new(HashMap::new()),
+        }
+    }
+    /// Riavvii effettuati dal thread corrente
+    pub fn restarts(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .get(&thread::current().id())
+            .map_or(0, |s| s.restarts)
+    }
+}
+
+impl<T, E, F> Epoch<T> for RestartEpoch<E, F>
+where
+    T: Unit + Genotype,
+    E: Epoch<T>,
+    F: Fn() -> T,
+{
+    fn epoch(&self, units: &mut Vec<LazyUnit<T>>, size: usize, r: &mut impl Rng) -> bool {
+        let cont = self.inner.epoch(units, size, r);
+        let best = units
+            .iter_mut()
+            .map(|u| u.fitness())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mut states = self.state.lock().unwrap();
+        let state = states.entry(thread::current().id()).or_default();
+        state.epoch += 1;
+        if self.params.diversity_every > 0 && state.epoch.is_multiple_of(self.params.diversity_every) {
+            let refs: Vec<&T> = units.iter().map(|u| &u.unit).collect();
+            debug!("Epoch {}: best {:.4}, {}", state.epoch, best, Diversity::measure(&refs));
+        }
+        if state.best.is_none_or(|b| best > b + f64::EPSILON) {
+            state.best = Some(best);
+            state.since_improvement = 0;
+        } else {
+            state.since_improvement += 1;
+        }
+        if self.params.patience > 0 && state.since_improvement >= self.params.patience {
+            units.sort_by(|a, b| {
+                b.fitness_lazy()
+                    .partial_cmp(&a.fitness_lazy())
+                    .unwrap_or(Ordering::Equal)
+            });
+            let keep = match self.params.policy {
+                RestartPolicy::Partial(fraction) => {
+                    ((units.len() as f64 * (1.0 - fraction)) as usize).max(self.params.elite)
+                }
+                RestartPolicy::Full => self.params.elite,
+            }
+            .min(units.len());
+            for unit in units.iter_mut().skip(keep) {
+                *unit = LazyUnit::from((self.new_unit)());
+            }
+            state.since_improvement = 0;
+            state.restarts += 1;
+            debug!(
+                "Epoch {}: stagnation at {:.4}, restart {} keeping {} units",
+                state.epoch, best, state.restarts, keep
+            );
+        }
+        cont
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use epoch::{ConfigurableEpoch, Replacement, Selection};
+    use rand::thread_rng;
+
+    /// Fitness costante: la popolazione stagna subito
+    #[derive(Clone)]
+    struct Flat(Vec<bool>);
+    impl Unit for Flat {
+        fn fitness(&self) -> f64 {
+            -1.0
+        }
+        fn breed_with(&self, _other: &Self) -> Self {
+            self.clone()
+        }
+    }
+    impl Genotype for Flat {
+        fn genotype(&self) -> &[bool] {
+            &self.0
+        }
+        fn col_len(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_diversity() {
+        let a = Flat(vec![true, false, true, false]);
+        let b = Flat(vec![true, false, false, true]);
+        let d = Diversity::measure(&[&a, &a, &b]);
+        //coppie: (a,a)=0, (a,b)=2, (a,b)=2
+        assert!((d.mean_hamming - 4.0 / 3.0).abs() < 1e-12);
+        assert!(d.distinct == 2);
+        assert!(d.column_entropy[0] == 0.0);
+        let h = binary_entropy(1.0 / 3.0);
+        assert!((d.column_entropy[1] - h).abs() < 1e-12);
+        let same = Diversity::measure(&[&a, &a]);
+        assert!(same.mean_hamming == 0.0 && same.distinct == 1);
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut rng = thread_rng();
+        let params = StagnationParameters::from_names(3, "full", 0.0, 2, 1).unwrap();
+        assert!(StagnationParameters::from_names(3, "never", 0.0, 2, 1).is_err());
+        let inner = ConfigurableEpoch::new(Selection::Tournament(2), Replacement::Generational, 0);
+        let epoch = RestartEpoch::new(inner, params, || Flat(vec![false; 4]));
+        let mut units: Vec<LazyUnit<Flat>> =
+            (0..10).map(|_| LazyUnit::from(Flat(vec![true; 4]))).collect();
+        //epoca 1: miglior fitness; epoche 2, 3, 4 senza miglioramenti
+        for _ in 0..4 {
+            epoch.epoch(&mut units, 10, &mut rng);
+        }
+        assert!(epoch.restarts() == 1);
+        let fresh = units.iter().filter(|u| u.unit.0 == vec![false; 4]).count();
+        assert!(fresh == 8);
+    }
+}
@@ -0,0 +1,282 @@
+//! Epoca configurabile: combina uno schema di selezione dei genitori
+//! con uno schema di sostituzione della popolazione.
+
+use rand::seq::sample_indices;
+use rand::Rng;
+use spiril::{epoch::Epoch, population::LazyUnit, unit::Unit};
+use std::cmp::Ordering;
+use std::mem;
+
+/// Scelta dei genitori
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    /// uniforme tra la frazione data dei migliori
+    Truncation(f64),
+    /// i due migliori tra tanti individui distinti estratti a caso
+    Tournament(usize),
+    /// proporzionale alla fitness, traslata in modo che il peggiore abbia peso minimo
+    Roulette,
+    /// proporzionale alla posizione in classifica
+    Rank,
+}
+
+/// Formazione della nuova popolazione
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replacement {
+    /// sopravvive la frazione data dei migliori, il resto sono figli
+    Survivors(f64),
+    /// solo figli, ma il migliore dei genitori sostituisce il figlio
+    /// peggiore se è migliore di tutti i figli
+    Generational,
+    /// (μ+λ): i migliori tra genitori e figli
+    Plus,
+    /// (μ,λ): i migliori tra i figli
+    Comma,
+    /// ogni figlio sostituisce il peggiore se non è peggiore di lui
+    SteadyState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfigurableEpoch {
+    pub selection: Selection,
+    pub replacement: Replacement,
+    /// figli generati per epoca (λ); 0 per usare la dimensione della popolazione.
+    /// Ignorato da `Survivors` e `Generational`.
+    pub offspring: usize,
+}
+
+impl ConfigurableEpoch {
+    pub fn new(selection: Selection, replacement: Replacement, offspring: usize) -> Self {
+        ConfigurableEpoch {
+            selection,
+            replacement,
+            offspring,
+        }
+    }
+    /// Costruisce l'epoca dai nomi usati sulla linea di comando
+    pub fn from_names(
+        selection: &str,
+        replacement: &str,
+        tournament_size: usize,
+        breed_factor: f64,
+        survival_factor: f64,
+        offspring: usize,
+    ) -> Result<Self, String> {
+        let selection = match selection {
+            "truncation" => Selection::Truncation(breed_factor),
+            "tournament" => Selection::Tournament(tournament_size),
+            "roulette" => Selection::Roulette,
+            "rank" => Selection::Rank,
+            _ => return Err(format!("Unknown selection scheme {}", selection)),
+        };
+        let replacement = match replacement {
+            "survivors" => Replacement::Survivors(survival_factor),
+            "generational" => Replacement::Generational,
+            "plus" => Replacement::Plus,
+            "comma" => Replacement::Comma,
+            "steady-state" => Replacement::SteadyState,
+            _ => return Err(format!("Unknown replacement scheme {}", replacement)),
+        };
+        Ok(ConfigurableEpoch::new(selection, replacement, offspring))
+    }
+
+    /// Sceglie due genitori da `units`, ordinati per fitness decrescente
+    fn select_parents<T: Unit>(&self, units: &[LazyUnit<T>], r: &mut impl Rng) -> (usize, usize) {
+        let len = units.len();
+        match self.selection {
+            Selection::Truncation(breed_factor) => {
+                let breeders = ((len as f64 * breed_factor) as usize).max(1).min(len);
+                (r.gen_range(0, breeders), r.gen_range(0, breeders))
+            }
+            Selection::Tournament(size) => {
+                let mut tournament = sample_indices(r, len, size.max(2).min(len));
+                tournament.sort();
+                (tournament[0], tournament[tournament.len().min(2) - 1])
+            }
+            Selection::Roulette => {
+                let worst = units[len - 1].fitness_lazy();
+                let range = units[0].fitness_lazy() - worst;
+                if range <= 0.0 {
+                    return (r.gen_range(0, len), r.gen_range(0, len));
+                }
+                //il peggiore ha comunque una piccola probabilità di essere scelto
+                let weights: Vec<f64> = units
+                    .iter()
+                    .map(|u| u.fitness_lazy() - worst + range / len as f64)
+                    .collect();
+                (pick_weighted(&weights, r), pick_weighted(&weights, r))
+            }
+            Selection::Rank => {
+                let weights: Vec<f64> = (0..len).map(|i| (len - i) as f64).collect();
+                (pick_weighted(&weights, r), pick_weighted(&weights, r))
+            }
+        }
+    }
+
+    fn breed<T: Unit>(&self, units: &[LazyUnit<T>], r: &mut impl Rng) -> LazyUnit<T> {
+        let (a, b) = self.select_parents(units, r);
+        let mut child = LazyUnit::from(units[a].unit.breed_with(&units[b].unit));
+        let _ = child.fitness();
+        child
+    }
+}
+
+/// Indice estratto con probabilità proporzionale a `weights`
+fn pick_weighted(weights: &[f64], r: &mut impl Rng) -> usize {
+    let tot: f64 = weights.iter().sum();
+    let mut x = r.gen_range(0.0, tot);
+    for (i, w) in weights.iter().enumerate() {
+        if x < *w {
+            return i;
+        }
+        x -= w;
+    }
+    weights.len() - 1
+}
+
+fn cmp_desc<T: Unit>(a: &LazyUnit<T>, b: &LazyUnit<T>) -> Ordering {
+    b.fitness_lazy()
+        .partial_cmp(&a.fitness_lazy())
+        .unwrap_or(Ordering::Equal)
+}
+
+impl<T: Unit + Clone> Epoch<T> for ConfigurableEpoch {
+    fn epoch(&self, units: &mut Vec<LazyUnit<T>>, size: usize, r: &mut impl Rng) -> bool {
+        for unit in units.iter_mut() {
+            let _ = unit.fitness();
+        }
+        units.sort_by(cmp_desc);
+        let lambda = if self.offspring == 0 { size } else { self.offspring };
+        let mut new_vec: Vec<LazyUnit<T>> = match self.replacement {
+            Replacement::Survivors(survival_factor) => {
+                let survivors = ((units.len() as f64 * survival_factor) as usize).min(size);
+                let children: Vec<_> = (survivors..size).map(|_| self.breed(units, r)).collect();
+                units.truncate(survivors);
+                let mut new_vec = mem::take(units);
+                new_vec.extend(children);
+                new_vec
+            }
+            Replacement::Generational => {
+                let mut new_vec: Vec<_> = (0..size).map(|_| self.breed(units, r)).collect();
+                new_vec.sort_by(cmp_desc);
+                if cmp_desc(&units[0], &new_vec[0]) == Ordering::Less {
+                    let mut best = LazyUnit::from(units[0].unit.clone());
+                    let _ = best.fitness();
+                    new_vec[size - 1] = best;
+                }
+                new_vec
+            }
+            Replacement::Plus => {
+                let children: Vec<_> = (0..lambda).map(|_| self.breed(units, r)).collect();
+                let mut new_vec = mem::take(units);
+                new_vec.extend(children);
+                new_vec
+            }
+            Replacement::Comma => (0..lambda.max(size)).map(|_| self.breed(units, r)).collect(),
+            Replacement::SteadyState => {
+                for _ in 0..lambda {
+                    let child = self.breed(units, r);
+                    let worst = units.len() - 1;
+                    if cmp_desc(&child, &units[worst]) != Ordering::Greater {
+                        units[worst] = child;
+                        units.sort_by(cmp_desc);
+                    }
+                }
+                mem::take(units)
+            }
+        };
+        new_vec.sort_by(cmp_desc);
+        new_vec.truncate(size);
+        *units = new_vec;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::thread_rng;
+    use spiril::population::Population;
+
+    /// Massimizza il numero di bit a 1 (fitness 0 quando sono tutti 1)
+    #[derive(Clone)]
+    struct OneMax(Vec<bool>);
+    impl Unit for OneMax {
+        fn fitness(&self) -> f64 {
+            -(self.0.iter().filter(|&&b| !b).count() as f64)
+        }
+        fn breed_with(&self, other: &Self) -> Self {
+            let mut rng = thread_rng();
+            let mut out: Vec<bool> = self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(&a, &b)| if rng.gen() { a } else { b })
+                .collect();
+            let i = rng.gen_range(0, out.len());
+            out[i] = !out[i];
+            OneMax(out)
+        }
+    }
+
+    #[test]
+    fn test_from_names() {
+        let e = ConfigurableEpoch::from_names("tournament", "plus", 5, 0.2, 0.8, 10).unwrap();
+        assert!(e == ConfigurableEpoch::new(Selection::Tournament(5), Replacement::Plus, 10));
+        let e = ConfigurableEpoch::from_names("truncation", "survivors", 5, 0.2, 0.8, 0).unwrap();
+        assert!(e.selection == Selection::Truncation(0.2));
+        assert!(e.replacement == Replacement::Survivors(0.8));
+        assert!(ConfigurableEpoch::from_names("best", "plus", 3, 0.2, 0.8, 0).is_err());
+        assert!(ConfigurableEpoch::from_names("rank", "all", 3, 0.2, 0.8, 0).is_err());
+    }
+
+    #[test]
+    fn test_epochs() {
+        let mut rng = thread_rng();
+        let selections = [
+            Selection::Truncation(0.2),
+            Selection::Tournament(3),
+            Selection::Tournament(50),
+            Selection::Roulette,
+            Selection::Rank,
+        ];
+        let replacements = [
+            Replacement::Survivors(0.8),
+            Replacement::Generational,
+            Replacement::Plus,
+            Replacement::Comma,
+            Replacement::SteadyState,
+        ];
+        for &selection in &selections {
+            for &replacement in &replacements {
+                let epoch = ConfigurableEpoch::new(selection, replacement, 7);
+                let mut units: Vec<LazyUnit<OneMax>> = (0..20)
+                    .map(|_| LazyUnit::from(OneMax((0..32).map(|_| rng.gen()).collect())))
+                    .collect();
+                for _ in 0..10 {
+                    let best = units.iter_mut().map(|u| u.fitness()).fold(-1000.0, f64::max);
+                    epoch.epoch(&mut units, 20, &mut rng);
+                    assert!(units.len() == 20);
+                    let new_best = units.iter_mut().map(|u| u.fitness()).fold(-1000.0, f64::max);
+                    if replacement != Replacement::Comma {
+                        assert!(new_best >= best, "{:?}", epoch);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_population() {
+        let mut rng = thread_rng();
+        let units: Vec<OneMax> = (0..30)
+            .map(|_| OneMax((0..16).map(|_| rng.gen()).collect()))
+            .collect();
+        let epoch = ConfigurableEpoch::new(Selection::Tournament(4), Replacement::Plus, 0);
+        let out = Population::new(units)
+            .set_size(30)
+            .epochs(200, &epoch)
+            .finish();
+        assert!(out.iter().any(|u| u.fitness() == 0.0));
+    }
+}
@@ -0,0 +1,68 @@
+//! Fitness definite fuori dal crate `oarray` e registrate per nome, così
+//! ogni programma le può selezionare con `--fitness`.
+
+use oarray::t_combinations::combinations_descent;
+use oarray::{register_fitness, Fitness, OArray};
+
+/// Registra le fitness di questo modulo; i programmi la chiamano all'avvio,
+/// prima di leggere il nome della fitness dalla linea di comando
+pub fn register_custom_fitness() {
+    register_fitness("DeltaMax", |exp| Box::new(DeltaMax(exp)));
+}
+
+/// Come `DeltaP`, ma conta solo l'insieme di t colonne peggiore invece di
+/// sommare su tutti: non premia chi bilancia molti insiemi lasciandone
+/// uno molto sbilanciato
+pub struct DeltaMax(pub u32);
+
+impl Fitness for DeltaMax {
+    fn fitness(&self, oa: &OArray) -> f64 {
+        let t = oa.target_t as usize;
+        let lambda = (oa.ngrande >> t) as i64;
+        let mut worst = 0f64;
+        //per ogni riga, i valori delle colonne scelte finora come intero
+        let start = (0, vec![0usize; oa.ngrande]);
+        combinations_descent(oa.k, t, 0, &start, &mut |col, &(depth, ref rows)| {
+            let col = &oa.d[col * oa.ngrande..(col + 1) * oa.ngrande];
+            let rows: Vec<usize> = rows
+                .iter()
+                .zip(col.iter())
+                .map(|(&r, &x)| r << 1 | x as usize)
+                .collect();
+            if depth + 1 == t {
+                let mut counts = vec![-lambda; 1 << t];
+                for &r in &rows {
+                    counts[r] += 1;
+                }
+                let tot: i64 = counts.iter().map(|c| c.abs().pow(self.0)).sum();
+                worst = worst.max((tot as f64).powf(1.0 / f64::from(self.0)));
+            }
+            (depth + 1, rows)
+        });
+        -worst
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use oarray::{fitness_by_name, FitnessFunction};
+    use rand::thread_rng;
+
+    #[test]
+    fn test_delta_max() {
+        register_custom_fitness();
+        let f = fitness_by_name("DeltaMax", 2).unwrap();
+        let mut r = thread_rng();
+        for _ in 0..50 {
+            let oa = OArray::new_random_balanced(8, 5, 2, &mut r, f);
+            let sum = FitnessFunction::DeltaP(2).fitness(&oa);
+            //il peggiore insieme di colonne pesa meno della somma su tutti
+            assert!(oa.fitness() <= 0.0 && oa.fitness() >= sum);
+            assert!((oa.fitness() == 0.0) == (sum == 0.0));
+        }
+        //con t = k c'è un solo insieme di colonne
+        let oa = OArray::new_random_balanced(8, 3, 3, &mut r, f);
+        assert!((oa.fitness() - FitnessFunction::DeltaP(2).fitness(&oa)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,239 @@
+//! Modello a isole: ogni thread fa evolvere una sottopopolazione e ogni
+//! `migration_interval` epoche invia i suoi `migration_size` individui
+//! migliori alle isole vicine secondo la topologia, dove sostituiscono
+//! i peggiori.
+
+use spiril::epoch::Epoch;
+use spiril::population::Population;
+use spiril::unit::Unit;
+use std::cmp::Ordering;
+use std::f64;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Barrier;
+use std::thread;
+
+/// Topologia delle migrazioni tra isole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    /// l'isola `i` invia all'isola `i + 1`
+    Ring,
+    /// ogni isola invia a tutte le altre
+    Full,
+}
+
+impl FromStr for Topology {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ring" => Ok(Topology::Ring),
+            "full" => Ok(Topology::Full),
+            _ => Err(format!("Unknown topology {}, expected ring or full", s)),
+        }
+    }
+}
+
+impl Topology {
+    /// Isole a cui `island` invia i propri migranti
+    pub fn neighbours(self, island: usize, islands: usize) -> Vec<usize> {
+        if islands < 2 {
+            return vec![];
+        }
+        match self {
+            Topology::Ring => vec![(island + 1) % islands],
+            Topology::Full => (0..islands).filter(|&j| j != island).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IslandParameters {
+    pub islands: usize,
+    pub topology: Topology,
+    /// epoche tra due migrazioni
+    pub migration_interval: usize,
+    /// individui inviati a ogni vicino
+    pub migration_size: usize,
+}
+
+fn sort_by_fitness<T: Unit>(units: &mut Vec<T>) {
+    let mut with_fitness: Vec<(f64, T)> = units.drain(..).map(|u| (u.fitness(), u)).collect();
+    with_fitness.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    units.extend(with_fitness.into_iter().map(|(_, u)| u));
+}
+
+/// Sostituisce i peggiori di `units` (ordinati per fitness decrescente)
+/// con `incoming`, tenendo comunque i `keep` migliori
+fn replace_worst<T>(units: &mut Vec<T>, mut incoming: Vec<T>, keep: usize) {
+    let keep = keep.min(units.len());
+    incoming.truncate(units.len() - keep);
+    let len = units.len() - incoming.len();
+    units.truncate(len);
+    units.extend(incoming);
+}
+
+/// Fa evolvere `populations.len()` isole per `epochs` epoche in thread
+/// separati. Si ferma in anticipo quando un'isola trova un individuo di
+/// fitness 0. `progress` riceve miglior fitness e media della prima isola.
+/// Restituisce l'unione delle popolazioni finali.
+pub fn run_islands<T, E, F>(
+    params: &IslandParameters,
+    epochs: usize,
+    pop_size: usize,
+    epoch: &E,
+    populations: Vec<Vec<T>>,
+    progress: F,
+) -> Vec<T>
+where
+    T: Unit + Clone + Send,
+    E: Epoch<T> + Sync,
+    F: FnMut(f64, f64) + Send,
+{
+    let islands = populations.len();
+    let interval = params.migration_interval.max(1);
+    let found = AtomicBool::new(false);
+    let barrier = Barrier::new(islands);
+    let (senders, receivers): (Vec<_>, Vec<_>) =
+        (0..islands).map(|_| channel::<Vec<T>>()).unzip();
+    let mut progress = Some(progress);
+
+    thread::scope(|s| {
+        let handles: Vec<_> = populations
+            .into_iter()
+            .zip(receivers)
+            .enumerate()
+            .map(|(island, (mut units, receiver))| {
+                let neighbours: Vec<Sender<Vec<T>>> = params
+                    .topology
+                    .neighbours(island, islands)
+                    .into_iter()
+                    .map(|j| senders[j].clone())
+                    .collect();
+                let mut progress = if island == 0 { progress.take() } else { None };
+                let found = &found;
+                let barrier = &barrier;
+                s.spawn(move || {
+                    let mut done = 0;
+                    while done < epochs {
+                        let n = interval.min(epochs - done);
+                        done += n;
+                        units = Population::new(units)
+                            .set_size(pop_size)
+                            .register_callback(Box::new(|best, mean| {
+                                if let Some(ref mut progress) = progress {
+                                    progress(best, mean);
+                                }
+                                if -best < f64::EPSILON {
+                                    found.store(true, AtomicOrdering::SeqCst);
+                                    return false;
+                                }
+                                true
+                            }))
+                            .epochs(n as u32, epoch)
+                            .finish();
+                        sort_by_fitness(&mut units);
+                        let m = params.migration_size.min(units.len());
+                        for sender in &neighbours {
+                            sender.send(units[..m].to_vec()).unwrap();
+                        }
+                        //tutti i migranti di questo turno sono stati inviati
+                        barrier.wait();
+                        let incoming: Vec<T> = receiver.try_iter().flatten().collect();
+                        replace_worst(&mut units, incoming, m);
+                        //found viene scritto solo durante l'evoluzione: tutte
+                        //le isole leggono lo stesso valore prima della barriera
+                        let stop = found.load(AtomicOrdering::SeqCst);
+                        barrier.wait();
+                        if stop {
+                            break;
+                        }
+                    }
+                    units
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{thread_rng, Rng};
+    use spiril::epoch::DefaultEpoch;
+
+    /// Massimizza il numero di bit a 1 (fitness 0 quando sono tutti 1)
+    #[derive(Clone)]
+    struct OneMax(Vec<bool>);
+    impl Unit for OneMax {
+        fn fitness(&self) -> f64 {
+            -(self.0.iter().filter(|&&b| !b).count() as f64)
+        }
+        fn breed_with(&self, other: &Self) -> Self {
+            let mut rng = thread_rng();
+            let mut out: Vec<bool> = self
+                .0
+                .iter()
+                .zip(other.0.iter())
+                .map(|(&a, &b)| if rng.gen() { a } else { b })
+                .collect();
+            let i = rng.gen_range(0, out.len());
+            out[i] = !out[i];
+            OneMax(out)
+        }
+    }
+
+    #[test]
+    fn test_neighbours() {
+        assert!(Topology::Ring.neighbours(3, 4) == vec![0]);
+        assert!(Topology::Full.neighbours(1, 3) == vec![0, 2]);
+        assert!(Topology::Full.neighbours(0, 1).is_empty());
+        assert!("full".parse::<Topology>() == Ok(Topology::Full));
+        assert!("star".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn test_replace_worst() {
+        let mut units = vec![5, 4, 3, 2, 1];
+        replace_worst(&mut units, vec![9, 9, 9, 9, 9, 9], 2);
+        assert!(units == vec![5, 4, 9, 9, 9]);
+        let mut units = vec![5, 4, 3];
+        replace_worst(&mut units, vec![9], 1);
+        assert!(units == vec![5, 4, 9]);
+    }
+
+    #[test]
+    fn test_islands() {
+        let mut rng = thread_rng();
+        for &topology in &[Topology::Ring, Topology::Full] {
+            let params = IslandParameters {
+                islands: 3,
+                topology,
+                migration_interval: 5,
+                migration_size: 2,
+            };
+            let populations: Vec<Vec<OneMax>> = (0..params.islands)
+                .map(|_| {
+                    (0..20)
+                        .map(|_| OneMax((0..16).map(|_| rng.gen()).collect()))
+                        .collect()
+                })
+                .collect();
+            let mut calls = 0;
+            let out = run_islands(
+                &params,
+                23,
+                20,
+                &DefaultEpoch::new(0.2, 0.8),
+                populations,
+                |_, _| calls += 1,
+            );
+            assert!(out.len() == 60);
+            assert!(calls > 0 && calls <= 23);
+        }
+    }
+}
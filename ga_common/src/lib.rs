@@ -0,0 +1,19 @@
+//! Componenti degli algoritmi evolutivi condivisi dai programmi di ricerca.
+#[macro_use]
+extern crate log;
+extern crate oarray;
+extern crate rand;
+extern crate spiril;
+
+pub mod crossover;
+pub mod diversity;
+pub mod epoch;
+pub mod fitness;
+pub mod island;
+pub mod mutation;
+pub use crossover::BalancedCrossover;
+pub use diversity::{Diversity, Genotype, RestartEpoch, RestartPolicy, StagnationParameters};
+pub use epoch::{ConfigurableEpoch, Replacement, Selection};
+pub use fitness::register_custom_fitness;
+pub use island::{run_islands, IslandParameters, Topology};
+pub use mutation::{BalancedMutation, Mutator, RateAdaptation, SwapCount};
@@ -0,0 +1,336 @@
+//! Mutazioni che preservano il bilanciamento delle colonne e adattamento
+//! della probabilità di mutazione.
+
+use rand::distributions::{Poisson, StandardNormal};
+use rand::Rng;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Numero di scambi effettuati da `BalancedMutation::Swap`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwapCount {
+    /// sempre lo stesso numero
+    Fixed(usize),
+    /// uniforme in 1..=n
+    Uniform(usize),
+    /// poissoniana di media data, almeno 1
+    Poisson(f64),
+    /// legge di potenza P(l) proporzionale a l^(-beta) sui valori ammessi,
+    /// come nel "fast GA": quasi sempre pochi scambi, ma a volte molti
+    PowerLaw(f64),
+}
+
+impl FromStr for SwapCount {
+    type Err = String;
+    /// `n`, `uniform:n`, `poisson:lambda` oppure `power:beta`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |_| format!("Invalid swap count {}", s);
+        let mut parts = s.splitn(2, ':');
+        match (parts.next().unwrap(), parts.next()) {
+            (n, None) => n.parse().map(SwapCount::Fixed).map_err(err),
+            ("uniform", Some(n)) => n.parse().map(SwapCount::Uniform).map_err(err),
+            ("poisson", Some(l)) => match l.parse() {
+                Ok(lambda) if lambda > 0.0 => Ok(SwapCount::Poisson(lambda)),
+                _ => Err(format!("Invalid swap count {}", s)),
+            },
+            ("power", Some(b)) => match b.parse() {
+                Ok(beta) if beta > 1.0 => Ok(SwapCount::PowerLaw(beta)),
+                _ => Err(format!("Invalid swap count {}", s)),
+            },
+            _ => Err(format!("Invalid swap count {}", s)),
+        }
+    }
+}
+
+impl SwapCount {
+    /// Numero di scambi; `max` limita solo `PowerLaw`
+    pub fn sample(self, max: usize, r: &mut impl Rng) -> usize {
+        match self {
+            SwapCount::Fixed(n) => n,
+            SwapCount::Uniform(n) => r.gen_range(1, n.max(1) + 1),
+            SwapCount::Poisson(lambda) => (r.sample(Poisson::new(lambda)) as usize).max(1),
+            SwapCount::PowerLaw(beta) => {
+                let max = max.max(1);
+                let weights: Vec<f64> = (1..=max).map(|l| (l as f64).powf(-beta)).collect();
+                let mut x = r.gen_range(0.0, weights.iter().sum::<f64>());
+                for (i, w) in weights.iter().enumerate() {
+                    if x < *w {
+                        return i + 1;
+                    }
+                    x -= w;
+                }
+                max
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BalancedMutation {
+    /// scambia coppie di celle con valori diversi
+    Swap(SwapCount),
+    /// inverte l'ordine di un segmento di righe
+    Inversion,
+    /// permuta a caso un segmento di righe
+    Scramble,
+    /// permuta a caso l'intera colonna
+    Rerandomize,
+}
+
+impl BalancedMutation {
+    /// Costruisce la mutazione dai nomi usati sulla linea di comando
+    pub fn from_name(name: &str, swaps: SwapCount) -> Result<Self, String> {
+        match name {
+            "swap" => Ok(BalancedMutation::Swap(swaps)),
+            "inversion" => Ok(BalancedMutation::Inversion),
+            "scramble" => Ok(BalancedMutation::Scramble),
+            "rerandomize" => Ok(BalancedMutation::Rerandomize),
+            _ => Err(format!("Unknown mutation {}", name)),
+        }
+    }
+
+    /// Muta una colonna
+    pub fn apply_col(self, col: &mut [bool], r: &mut impl Rng) {
+        let n = col.len();
+        if n < 2 {
+            return;
+        }
+        match self {
+            BalancedMutation::Swap(swaps) => {
+                for _ in 0..swaps.sample(n / 2, r) {
+                    swap_different(col, r);
+                }
+            }
+            BalancedMutation::Inversion => {
+                let (start, end) = segment(n, r);
+                col[start..end].reverse();
+            }
+            BalancedMutation::Scramble => {
+                let (start, end) = segment(n, r);
+                r.shuffle(&mut col[start..end]);
+            }
+            BalancedMutation::Rerandomize => r.shuffle(col),
+        }
+    }
+}
+
+/// Segmento casuale di almeno due righe
+fn segment(n: usize, r: &mut impl Rng) -> (usize, usize) {
+    let start = r.gen_range(0, n - 1);
+    let end = r.gen_range(start + 2, n + 1);
+    (start, end)
+}
+
+/// Scambia una cella a 0 con una a 1, scelte a caso; non fa nulla se la
+/// colonna è costante
+pub fn swap_different(col: &mut [bool], r: &mut impl Rng) {
+    let coord1 = r.gen_range(0, col.len());
+    let others: Vec<usize> = (0..col.len()).filter(|&j| col[j] != col[coord1]).collect();
+    if let Some(&coord2) = r.choose(&others) {
+        col.swap(coord1, coord2);
+    }
+}
+
+/// Modo in cui varia la probabilità di mutazione durante la run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateAdaptation {
+    Fixed,
+    /// regola di 1/5: la probabilità cresce se più di un figlio su cinque
+    /// migliora i genitori, altrimenti cala
+    OneFifth,
+    /// ogni individuo porta la sua probabilità, che il figlio eredita
+    /// moltiplicata per un fattore log-normale
+    SelfAdaptive,
+}
+
+impl FromStr for RateAdaptation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(RateAdaptation::Fixed),
+            "one-fifth" => Ok(RateAdaptation::OneFifth),
+            "self-adaptive" => Ok(RateAdaptation::SelfAdaptive),
+            _ => Err(format!("Unknown rate adaptation {}", s)),
+        }
+    }
+}
+
+const MIN_RATE: f64 = 0.001;
+/// fattore di variazione della regola di 1/5
+const ONE_FIFTH_FACTOR: f64 = 0.85;
+/// figli valutati prima di aggiornare la probabilità
+const ONE_FIFTH_WINDOW: usize = 50;
+/// deviazione standard del logaritmo del fattore di auto-adattamento
+const SELF_ADAPTIVE_TAU: f64 = 0.2;
+
+struct OneFifthState {
+    rate: f64,
+    trials: usize,
+    successes: usize,
+}
+
+/// Mutazione e adattamento della sua probabilità, condivisi da una popolazione
+#[derive(Clone)]
+pub struct Mutator {
+    pub op: BalancedMutation,
+    pub adaptation: RateAdaptation,
+    one_fifth: Arc<Mutex<OneFifthState>>,
+}
+
+impl Mutator {
+    pub fn new(op: BalancedMutation, adaptation: RateAdaptation, initial_rate: f64) -> Self {
+        Mutator {
+            op,
+            adaptation,
+            one_fifth: Arc::new(Mutex::new(OneFifthState {
+                rate: initial_rate,
+                trials: 0,
+                successes: 0,
+            })),
+        }
+    }
+
+    /// Probabilità di mutazione di un figlio il cui genitore ha `parent_rate`
+    pub fn child_rate(&self, parent_rate: f64, r: &mut impl Rng) -> f64 {
+        match self.adaptation {
+            RateAdaptation::Fixed => parent_rate,
+            RateAdaptation::OneFifth => self.one_fifth.lock().unwrap().rate,
+            RateAdaptation::SelfAdaptive => {
+                let factor = (SELF_ADAPTIVE_TAU * r.sample(StandardNormal)).exp();
+                (parent_rate * factor).clamp(MIN_RATE, 1.0)
+            }
+        }
+    }
+
+    /// Con la regola di 1/5, registra se un figlio ha migliorato i genitori
+    pub fn report(&self, success: bool) {
+        if self.adaptation != RateAdaptation::OneFifth {
+            return;
+        }
+        let mut state = self.one_fifth.lock().unwrap();
+        state.trials += 1;
+        if success {
+            state.successes += 1;
+        }
+        if state.trials == ONE_FIFTH_WINDOW {
+            let ratio = state.successes as f64 / state.trials as f64;
+            if ratio > 0.2 {
+                state.rate = (state.rate / ONE_FIFTH_FACTOR).min(1.0);
+            } else if ratio < 0.2 {
+                state.rate = (state.rate * ONE_FIFTH_FACTOR).max(MIN_RATE);
+            }
+            state.trials = 0;
+            state.successes = 0;
+        }
+    }
+
+    /// Con la regola di 1/5, probabilità di mutazione corrente
+    pub fn rate(&self) -> f64 {
+        self.one_fifth.lock().unwrap().rate
+    }
+
+    /// Muta ogni colonna di `d`, lunga `ngrande`, con probabilità `prob`
+    pub fn mutate(&self, d: &mut [bool], ngrande: usize, prob: f64, r: &mut impl Rng) {
+        for col in d.chunks_mut(ngrande) {
+            if r.gen_range::<f64>(0.0, 1.0) < prob {
+                self.op.apply_col(col, r);
+            }
+        }
+    }
+}
+
+/// Uno scambio per colonna a probabilità fissa
+impl Default for Mutator {
+    fn default() -> Self {
+        Mutator::new(BalancedMutation::Swap(SwapCount::Fixed(1)), RateAdaptation::Fixed, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::thread_rng;
+
+    fn count_ones(v: &[bool]) -> usize {
+        v.iter().filter(|&&x| x).count()
+    }
+
+    #[test]
+    fn test_mutations() {
+        let mut r = thread_rng();
+        let ops = [
+            BalancedMutation::Swap(SwapCount::Fixed(3)),
+            BalancedMutation::Swap(SwapCount::Uniform(4)),
+            BalancedMutation::Swap(SwapCount::Poisson(2.0)),
+            BalancedMutation::Swap(SwapCount::PowerLaw(1.5)),
+            BalancedMutation::Inversion,
+            BalancedMutation::Scramble,
+            BalancedMutation::Rerandomize,
+        ];
+        for &op in &ops {
+            for _ in 0..100 {
+                let mut col: Vec<bool> = (0..16).map(|i| i % 2 == 0).collect();
+                r.shuffle(&mut col);
+                op.apply_col(&mut col, &mut r);
+                assert!(count_ones(&col) == 8, "{:?}", op);
+            }
+        }
+        //le colonne costanti non bloccano lo scambio
+        let mut constant = vec![true; 8];
+        BalancedMutation::Swap(SwapCount::Fixed(2)).apply_col(&mut constant, &mut r);
+        assert!(constant == vec![true; 8]);
+        let mut single = vec![true];
+        BalancedMutation::Inversion.apply_col(&mut single, &mut r);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("4".parse::<SwapCount>() == Ok(SwapCount::Fixed(4)));
+        assert!("uniform:3".parse::<SwapCount>() == Ok(SwapCount::Uniform(3)));
+        assert!("poisson:1.5".parse::<SwapCount>() == Ok(SwapCount::Poisson(1.5)));
+        assert!("poisson".parse::<SwapCount>().is_err());
+        assert!("poisson:0".parse::<SwapCount>().is_err());
+        assert!("normal:2".parse::<SwapCount>().is_err());
+        assert!("power:2.5".parse::<SwapCount>() == Ok(SwapCount::PowerLaw(2.5)));
+        assert!("power:1".parse::<SwapCount>().is_err());
+        assert!(BalancedMutation::from_name("inversion", SwapCount::Fixed(1)).is_ok());
+        assert!(BalancedMutation::from_name("flip", SwapCount::Fixed(1)).is_err());
+        assert!("one-fifth".parse::<RateAdaptation>() == Ok(RateAdaptation::OneFifth));
+    }
+
+    #[test]
+    fn test_power_law() {
+        let mut r = thread_rng();
+        let samples: Vec<usize> = (0..2000)
+            .map(|_| SwapCount::PowerLaw(1.5).sample(50, &mut r))
+            .collect();
+        assert!(samples.iter().all(|&l| (1..=50).contains(&l)));
+        let ones = samples.iter().filter(|&&l| l == 1).count();
+        //P(1) = 1 / sum l^-1.5, circa 0.4
+        assert!(ones > 600 && ones < 1000, "{}", ones);
+        assert!(samples.iter().any(|&l| l > 10));
+        assert!(SwapCount::PowerLaw(3.0).sample(0, &mut r) == 1);
+    }
+
+    #[test]
+    fn test_one_fifth() {
+        let mut r = thread_rng();
+        let m = Mutator::new(BalancedMutation::Inversion, RateAdaptation::OneFifth, 0.5);
+        for _ in 0..ONE_FIFTH_WINDOW {
+            m.report(false);
+        }
+        assert!((m.child_rate(0.5, &mut r) - 0.5 * ONE_FIFTH_FACTOR).abs() < 1e-12);
+        for i in 0..ONE_FIFTH_WINDOW {
+            m.report(i % 2 == 0);
+        }
+        assert!((m.child_rate(0.1, &mut r) - 0.5).abs() < 1e-12);
+
+        let fixed = Mutator::new(BalancedMutation::Inversion, RateAdaptation::Fixed, 0.5);
+        assert!(fixed.child_rate(0.3, &mut r) == 0.3);
+        let adaptive = Mutator::new(BalancedMutation::Inversion, RateAdaptation::SelfAdaptive, 0.5);
+        for _ in 0..100 {
+            let rate = adaptive.child_rate(0.3, &mut r);
+            assert!((MIN_RATE..=1.0).contains(&rate));
+        }
+    }
+}
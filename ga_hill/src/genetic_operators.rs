@@ -1,3 +1,4 @@
+use ga_common::{BalancedCrossover, Mutator, RateAdaptation};
 use oarray::OArray;
 use rand::thread_rng;
 use rand::Rng;
@@ -10,45 +11,18 @@ use std::iter;
 pub struct GAOArray {
     pub oa: OArray,
     pub mutation_prob: f64,
+    pub crossover: BalancedCrossover,
+    pub mutator: Mutator,
+    /// scambi per insieme di miglioramento e insiemi applicati a ogni figlio
+    pub hill_swaps: usize,
+    pub hill_steps: usize,
 }
 
 impl GAOArray {
-    /// Scambia due coordinate nel vettore con probabiltà `prob`,
-    /// usando `rng`.
+    /// Muta ogni colonna con probabilità `prob`, usando `rng`.
     fn mutate_with_prob(&mut self, prob: f64, rng: &mut impl Rng) {
         let n = self.oa.ngrande;
-        for col in self.oa.iter_cols_mut() {
-            if rng.gen_range::<f64>(0.0, 1.0) < prob {
-                //pick random coordinate to mutate
-                let coord1 = rng.gen_range(0, n);
-                //pick other coordinate to swap
-                let mut coord2 = rng.gen_range(0, n);
-                while col[coord2] == col[coord1] {
-                    coord2 = rng.gen_range(0, n);
-                }
-                col.swap(coord1, coord2);
-            }
-        }
-    }
-}
-/// Unisce due OArray in in modo che il risultato sia bilanciato
-fn balanced_crossover(a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
-    let ngrande = a.len();
-    let balance = ngrande / 2;
-    let mut pos: Vec<_> = (0..ngrande).collect();
-    r.shuffle(&mut pos);
-    let mut cnt = [balance, balance];
-    for j in pos {
-        let choice = if cnt[0] == 0 {
-            true
-        } else if cnt[1] == 0 {
-            false
-        } else {
-            *r.choose(&[a[j], b[j]]).unwrap()
-        };
-
-        out[j] = choice;
-        cnt[choice as usize] -= 1;
+        self.mutator.mutate(&mut self.oa.d, n, prob, rng);
     }
 }
 
@@ -67,18 +41,23 @@ impl Unit for GAOArray {
             iter::repeat(false).take(oa.ngrande * oa.k).collect(),
             oa.fitness_f
         );
-        for (col1, (col2, col3)) in oa
-            .iter_cols()
-            .zip(other.oa.iter_cols().zip(out_inner.iter_cols_mut()))
-        {
-            balanced_crossover(col1, col2, col3, &mut rng);
-        }
+        self.crossover
+            .apply(&oa.d, &other.oa.d, &mut out_inner.d, oa.ngrande, &mut rng);
+        let mutation_prob = self.mutator.child_rate(self.mutation_prob, &mut rng);
         let mut out = GAOArray {
             oa: out_inner,
-            mutation_prob: self.mutation_prob,
+            mutation_prob,
+            crossover: self.crossover,
+            mutator: self.mutator.clone(),
+            hill_swaps: self.hill_swaps,
+            hill_steps: self.hill_steps,
         };
-        out.mutate_with_prob(self.mutation_prob, &mut rng);
-        out.hill_climb(1.0);
+        out.mutate_with_prob(mutation_prob, &mut rng);
+        out.oa.walsh_hill_climb(self.hill_swaps, self.hill_steps);
+        if self.mutator.adaptation == RateAdaptation::OneFifth {
+            let best_parent = self.fitness().max(other.fitness());
+            self.mutator.report(out.fitness() > best_parent);
+        }
         out
     }
 
@@ -87,14 +66,25 @@ impl Unit for GAOArray {
         self.oa.fitness()
     }
 }
+
+/// Unità di prova su un array bilanciato casuale, con gli operatori di default
+#[cfg(test)]
+pub(crate) fn test_unit(ngrande: usize, k: usize, t: u32, r: &mut impl Rng) -> GAOArray {
+    use oarray::FitnessFunction;
+    GAOArray {
+        oa: OArray::new_random_balanced(ngrande, k, t, r, FitnessFunction::DeltaFast),
+        mutation_prob: 0.5,
+        crossover: BalancedCrossover::Uniform,
+        mutator: Mutator::default(),
+        hill_swaps: 2,
+        hill_steps: 1,
+    }
+}
+
 #[test]
 fn mutation() {
-    use oarray::FitnessFunction;
     let mut r = thread_rng();
-    let mut a = GAOArray {
-        oa: OArray::new_random_balanced(8, 4, 3, &mut r, FitnessFunction::DeltaFast),
-        mutation_prob: 0.5,
-    };
+    let mut a = test_unit(8, 4, 3, &mut r);
     let b = a.clone();
     assert!(a.oa.d == b.oa.d);
     a.mutate_with_prob(1.0, &mut r);
@@ -106,21 +96,12 @@ fn mutation() {
 
 #[test]
 fn balanced_crossover_test() {
-    use oarray::FitnessFunction;
+    use ga_common::crossover::balanced_crossover;
     let mut r = thread_rng();
     for _ in 0..100 {
-        let a = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
-        let b = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
-        let mut c = GAOArray {
-            oa: OArray::new_random_balanced(8, 1, 1, &mut r, FitnessFunction::DeltaFast),
-            mutation_prob: 0.5,
-        };
+        let a = test_unit(8, 1, 1, &mut r);
+        let b = test_unit(8, 1, 1, &mut r);
+        let mut c = test_unit(8, 1, 1, &mut r);
         assert!(is_balanced(&a.oa.d));
         assert!(is_balanced(&b.oa.d));
         balanced_crossover(&a.oa.d, &b.oa.d, &mut c.oa.d, &mut r);
@@ -1,3 +1,4 @@
+extern crate ga_common;
 extern crate oarray;
 extern crate pbr;
 extern crate rand;
@@ -8,6 +9,7 @@ extern crate log;
 extern crate simplelog;
 
 use clap::{App, Arg};
+use ga_common::{BalancedCrossover, BalancedMutation, ConfigurableEpoch, RateAdaptation, SwapCount};
 use simplelog::*;
 use std::fs::File;
 use std::thread;
@@ -15,7 +17,6 @@ use std::thread;
 mod run;
 //mod epoch;
 mod genetic_operators;
-mod hill_climb;
 use run::run;
 
 macro_rules! get_arg {
@@ -29,6 +30,8 @@ macro_rules! get_arg {
 }
 
 fn main() {
+    ga_common::register_custom_fitness();
+    let fitness_help = format!("Fitness function [{}]", oarray::fitness_names().join(", "));
     let matches = App::new("GA run")
         .about("Run the Genetic Algorithm")
         .arg(
@@ -97,7 +100,7 @@ fn main() {
         .arg(
             Arg::with_name("fitness")
                 .long("fitness")
-                .help("Fitness function [Delta, DeltaFast, Walsh]")
+                .help(&fitness_help)
                 .default_value("DeltaFast")
         )
         .arg(
@@ -106,19 +109,75 @@ fn main() {
                 .help("Exponent for the fitness function")
                 .default_value("2")
         )
+        .arg(
+            Arg::with_name("selection")
+                .long("selection")
+                .help("Parent selection scheme [truncation, tournament, roulette, rank]")
+                .default_value("truncation"),
+        )
+        .arg(
+            Arg::with_name("tournament-size")
+                .long("tournament-size")
+                .help("Number of units competing in each tournament")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("replacement")
+                .long("replacement")
+                .help("Replacement scheme [survivors, generational, plus, comma, steady-state]")
+                .default_value("survivors"),
+        )
+        .arg(
+            Arg::with_name("offspring")
+                .long("offspring")
+                .help("Offspring per epoch for plus, comma and steady-state (0: pop-size)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("crossover")
+                .long("crossover")
+                .help("Balanced crossover [uniform, one-point, two-point, map-of-ones, column-exchange, row-block]")
+                .default_value("uniform"),
+        )
+        .arg(
+            Arg::with_name("mutation")
+                .long("mutation")
+                .help("Mutation applied to each column [swap, inversion, scramble, rerandomize]")
+                .default_value("swap"),
+        )
+        .arg(
+            Arg::with_name("swap-count")
+                .long("swap-count")
+                .help("Swaps per swap mutation: n, uniform:n (1..=n), poisson:lambda or power:beta")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("rate-adaptation")
+                .long("rate-adaptation")
+                .help("Mutation probability adaptation [fixed, one-fifth, self-adaptive]")
+                .default_value("fixed"),
+        )
+        .arg(
+            Arg::with_name("hill-swaps")
+                .long("hill-swaps")
+                .help("Maximum number of truth table swaps in an improvement set")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::with_name("hill-steps")
+                .long("hill-steps")
+                .help("Improvement sets applied to each offspring")
+                .default_value("1"),
+        )
         .get_matches();
 
     let ngrande = get_arg!(matches, "N", usize);
 
-    let f = match matches.value_of("fitness").unwrap() {
-        "Delta" => oarray::FitnessFunction::Delta,
-        "DeltaFast" => oarray::FitnessFunction::DeltaFast,
-        "Cidev" => oarray::FitnessFunction::Cidev,
-        "SheerLuck" => oarray::FitnessFunction::SheerLuck,
-        "Walsh" => oarray::FitnessFunction::Walsh(get_arg!(matches, "fitness-exp", u32)),
-        "WalshFast" => oarray::FitnessFunction::WalshFaster(get_arg!(matches, "fitness-exp", u32)),
-        _ => panic!("Invalid function name")
-    };
+    let f = oarray::fitness_by_name(
+        matches.value_of("fitness").unwrap(),
+        get_arg!(matches, "fitness-exp", u32),
+    )
+    .expect("Invalid function name");
     let params = run::RunParameters {
         ngrande,
         k: get_arg!(matches, "k", usize),
@@ -126,9 +185,25 @@ fn main() {
         epochs: get_arg!(matches, "epochs", usize),
         pop_size: get_arg!(matches, "pop-size", usize),
         mutation_prob: get_arg!(matches, "mutation-prob", f64),
-        breed_factor: get_arg!(matches, "breed-factor", f64),
-        survival_factor: get_arg!(matches, "survival-factor", f64),
-        fitness_f : f
+        epoch: ConfigurableEpoch::from_names(
+            matches.value_of("selection").unwrap(),
+            matches.value_of("replacement").unwrap(),
+            get_arg!(matches, "tournament-size", usize),
+            get_arg!(matches, "breed-factor", f64),
+            get_arg!(matches, "survival-factor", f64),
+            get_arg!(matches, "offspring", usize),
+        )
+        .expect("Invalid epoch configuration"),
+        crossover: get_arg!(matches, "crossover", BalancedCrossover),
+        mutation: BalancedMutation::from_name(
+            matches.value_of("mutation").unwrap(),
+            get_arg!(matches, "swap-count", SwapCount),
+        )
+        .expect("Invalid mutation"),
+        rate_adaptation: get_arg!(matches, "rate-adaptation", RateAdaptation),
+        fitness_f : f,
+        hill_swaps: get_arg!(matches, "hill-swaps", usize),
+        hill_steps: get_arg!(matches, "hill-steps", usize),
     };
     let runs = get_arg!(matches, "runs", usize);
     let threads = get_arg!(matches, "threads", usize);
@@ -155,6 +230,12 @@ fn main() {
         params.k,
         params.t
     );
+    if let Some(violation) = oarray::bounds::check_parameters(params.ngrande, params.k, params.t) {
+        warn!(
+            "OA[N: {}, k: {}, s: 2, t: {}] cannot exist: {}",
+            params.ngrande, params.k, params.t, violation
+        );
+    }
     debug!("{:#?}", params);
 
     let show_progress = threads == 1;
@@ -1,10 +1,9 @@
-use spiril::{population::Population, unit::Unit, epoch::DefaultEpoch};
+use spiril::{population::Population, unit::Unit};
 use std::f64;
 
-//mod epoch;
-//use epoch::TournamentEpoch;
 
 use genetic_operators::GAOArray;
+use ga_common::{BalancedCrossover, BalancedMutation, ConfigurableEpoch, Mutator, RateAdaptation};
 use oarray::{OArray, FitnessFunction};
 use rand::thread_rng;
 use pbr::ProgressBar;
@@ -16,27 +15,35 @@ pub(crate) struct RunParameters {
     pub t: u32,
     pub pop_size: usize,
     pub mutation_prob: f64,
-    pub breed_factor: f64,
-    pub survival_factor: f64,
+    pub epoch: ConfigurableEpoch,
+    pub crossover: BalancedCrossover,
+    pub mutation: BalancedMutation,
+    pub rate_adaptation: RateAdaptation,
     pub epochs: usize,
-    pub fitness_f: FitnessFunction
+    pub fitness_f: FitnessFunction,
+    pub hill_swaps: usize,
+    pub hill_steps: usize,
 }
 
 pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
     let mut rng = thread_rng();
     //let ngrande = 2usize.pow(p.n as u32);
     let ngrande = p.ngrande;
+    let mutator = Mutator::new(p.mutation, p.rate_adaptation, p.mutation_prob);
     let units: Vec<GAOArray> = (0..p.pop_size)
         .map(|_i| GAOArray {
             oa: OArray::new_random_balanced(ngrande, p.k, p.t, &mut rng, p.fitness_f),
             mutation_prob: p.mutation_prob,
+            crossover: p.crossover,
+            mutator: mutator.clone(),
+            hill_swaps: p.hill_swaps,
+            hill_steps: p.hill_steps,
         })
         .collect();
 
     let mut pbar = ProgressBar::new(p.epochs as u64);
 
-    //let epoch = TournamentEpoch::new();
-    let epoch = DefaultEpoch::new(p.breed_factor, p.survival_factor);
+    let epoch = p.epoch;
     let f = Population::new(units)
         .set_size(p.pop_size)
         .register_callback(Box::new(move |i, j| {
@@ -1,5 +1,5 @@
-use oarray::FitnessFunction::*;
-use oarray::OArray;
+use ga_common::{BalancedCrossover, Mutator, RateAdaptation};
+use oarray::{Fitness, OArray};
 use rand::thread_rng;
 use rand::Rng;
 use spiril::unit::Unit;
@@ -12,11 +12,19 @@ pub struct IncGAOArray<'a> {
     pub partial: &'a OArray,
     pub last_col: Vec<bool>,
     pub mutation_prob: f64,
+    pub crossover: BalancedCrossover,
+    pub mutator: Mutator,
     pub target_k: usize,
 }
 
 impl<'a> IncGAOArray<'a> {
-    pub fn new(partial: &'a OArray, mutation_prob: f64, target_k: usize) -> Self {
+    pub fn new(
+        partial: &'a OArray,
+        mutation_prob: f64,
+        crossover: BalancedCrossover,
+        mutator: Mutator,
+        target_k: usize,
+    ) -> Self {
         let mut r = thread_rng();
         assert!(-partial.fitness() < f64::EPSILON);
         let mut last_col: Vec<bool> = [true, false]
@@ -30,24 +38,16 @@ impl<'a> IncGAOArray<'a> {
             partial,
             last_col,
             mutation_prob,
+            crossover,
+            mutator,
             target_k,
         }
     }
 
-    /// Scambia due coordinate nel vettore con probabiltà `prob`,
-    /// usando `rng`.
+    /// Muta l'ultima colonna con probabilità `prob`, usando `rng`.
     fn mutate_with_prob(&mut self, prob: f64, rng: &mut impl Rng) {
         let n = self.partial.ngrande;
-        if rng.gen_range::<f64>(0.0, 1.0) < prob {
-            //pick random coordinate to mutate
-            let coord1 = rng.gen_range(0, n);
-            //pick other coordinate to swap
-            let mut coord2 = rng.gen_range(0, n);
-            while self.last_col[coord2] == self.last_col[coord1] {
-                coord2 = rng.gen_range(0, n);
-            }
-            self.last_col.swap(coord1, coord2);
-        }
+        self.mutator.mutate(&mut self.last_col, n, prob, rng);
     }
     pub fn complete_oa(&self) -> OArray {
         let mut other = self.partial.clone();
@@ -56,26 +56,6 @@ impl<'a> IncGAOArray<'a> {
         other
     }
 }
-/// Unisce due OArray in in modo che il risultato sia bilanciato
-fn balanced_crossover(a: &[bool], b: &[bool], out: &mut [bool], r: &mut impl Rng) {
-    let ngrande = a.len();
-    let balance = ngrande / 2;
-    let mut pos: Vec<_> = (0..ngrande).collect();
-    r.shuffle(&mut pos);
-    let mut cnt = [balance, balance];
-    for j in pos {
-        let choice = if cnt[0] == 0 {
-            true
-        } else if cnt[1] == 0 {
-            false
-        } else {
-            *r.choose(&[a[j], b[j]]).unwrap()
-        };
-
-        out[j] = choice;
-        cnt[choice as usize] -= 1;
-    }
-}
 
 // implement trait functions mutate and calculate_fitness:
 impl<'a> Unit for IncGAOArray<'a> {
@@ -84,17 +64,21 @@ impl<'a> Unit for IncGAOArray<'a> {
         //GA crossover and mutation operators are applied
         //component-wise on the last col
         let mut out = self.clone();
-        balanced_crossover(&self.last_col, &other.last_col, &mut out.last_col, &mut rng);
-        out.mutate_with_prob(self.mutation_prob, &mut rng);
+        self.crossover
+            .apply_col(&self.last_col, &other.last_col, &mut out.last_col, &mut rng);
+        out.mutation_prob = self.mutator.child_rate(self.mutation_prob, &mut rng);
+        let mutation_prob = out.mutation_prob;
+        out.mutate_with_prob(mutation_prob, &mut rng);
+        if self.mutator.adaptation == RateAdaptation::OneFifth {
+            let best_parent = self.fitness().max(other.fitness());
+            self.mutator.report(out.fitness() > best_parent);
+        }
         out
     }
 
     fn fitness(&self) -> f64 {
-        match self.partial.fitness_f {
-            Walsh(x) => self.partial.walsh_incremental(x, &self.last_col),
-            WalshFaster(x) => self.partial.walsh_incremental_faster(x, &self.last_col),
-            DeltaFast => self.complete_oa().delta_incremental_faster(),
-            _ => self.complete_oa().fitness(),
-        }
+        self.partial
+            .fitness_f
+            .incremental_fitness(self.partial, &self.last_col)
     }
 }
@@ -1,4 +1,5 @@
 extern crate clap;
+extern crate ga_common;
 extern crate oarray;
 extern crate pbr;
 extern crate rand;
@@ -9,6 +10,7 @@ extern crate simplelog;
 extern crate streaming_iterator;
 
 use clap::{App, Arg};
+use ga_common::{BalancedCrossover, BalancedMutation, ConfigurableEpoch, RateAdaptation, SwapCount};
 use simplelog::*;
 use std::fs::File;
 use std::thread;
@@ -16,7 +18,6 @@ use std::thread;
 mod genetic_operators;
 mod run;
 use run::run;
-mod epoch;
 
 macro_rules! get_arg {
     ($matches: expr, $x:expr, $type: ident) => {
@@ -29,6 +30,8 @@ macro_rules! get_arg {
 }
 
 fn main() {
+    ga_common::register_custom_fitness();
+    let fitness_help = format!("Fitness function [{}]", oarray::fitness_names().join(", "));
     let matches = App::new("GA run")
         .about("Run the Genetic Algorithm")
         .arg(
@@ -91,7 +94,7 @@ fn main() {
         .arg(
             Arg::with_name("fitness")
                 .long("fitness")
-                .help("Fitness function [Delta, DeltaFast, Walsh]")
+                .help(&fitness_help)
                 .default_value("DeltaFast"),
         )
         .arg(
@@ -100,17 +103,73 @@ fn main() {
                 .help("Exponent for the fitness function")
                 .default_value("2"),
         )
+        .arg(
+            Arg::with_name("breed-factor")
+                .long("breed-factor")
+                .help("Fraction of breeders (the most fit will be chosen) in the total population ")
+                .default_value("0.2"),
+        )
+        .arg(
+            Arg::with_name("survival-factor")
+                .long("survival-factor")
+                .help("Fractions of individuals who will survive to the next epoch")
+                .default_value("0.8"),
+        )
+        .arg(
+            Arg::with_name("selection")
+                .long("selection")
+                .help("Parent selection scheme [truncation, tournament, roulette, rank]")
+                .default_value("truncation"),
+        )
+        .arg(
+            Arg::with_name("tournament-size")
+                .long("tournament-size")
+                .help("Number of units competing in each tournament")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("replacement")
+                .long("replacement")
+                .help("Replacement scheme [survivors, generational, plus, comma, steady-state]")
+                .default_value("survivors"),
+        )
+        .arg(
+            Arg::with_name("offspring")
+                .long("offspring")
+                .help("Offspring per epoch for plus, comma and steady-state (0: pop-size)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("crossover")
+                .long("crossover")
+                .help("Balanced crossover [uniform, one-point, two-point, map-of-ones, column-exchange, row-block]")
+                .default_value("uniform"),
+        )
+        .arg(
+            Arg::with_name("mutation")
+                .long("mutation")
+                .help("Mutation applied to each column [swap, inversion, scramble, rerandomize]")
+                .default_value("swap"),
+        )
+        .arg(
+            Arg::with_name("swap-count")
+                .long("swap-count")
+                .help("Swaps per swap mutation: n, uniform:n (1..=n), poisson:lambda or power:beta")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("rate-adaptation")
+                .long("rate-adaptation")
+                .help("Mutation probability adaptation [fixed, one-fifth, self-adaptive]")
+                .default_value("fixed"),
+        )
         .get_matches();
 
-    let f = match matches.value_of("fitness").unwrap() {
-        "Delta" => oarray::FitnessFunction::Delta,
-        "DeltaFast" => oarray::FitnessFunction::DeltaFast,
-        "Cidev" => oarray::FitnessFunction::Cidev,
-        "SheerLuck" => oarray::FitnessFunction::SheerLuck,
-        "Walsh" => oarray::FitnessFunction::Walsh(get_arg!(matches, "fitness-exp", u32)),
-        "WalshFast" => oarray::FitnessFunction::WalshFaster(get_arg!(matches, "fitness-exp", u32)),
-        _ => panic!("Invalid function name"),
-    };
+    let f = oarray::fitness_by_name(
+        matches.value_of("fitness").unwrap(),
+        get_arg!(matches, "fitness-exp", u32),
+    )
+    .expect("Invalid function name");
     let params = run::RunParameters {
         ngrande: get_arg!(matches, "N", usize),
         k: get_arg!(matches, "k", usize),
@@ -119,6 +178,22 @@ fn main() {
         pop_size: get_arg!(matches, "pop-size", usize),
         mutation_prob: get_arg!(matches, "mutation-prob", f64),
         silent: get_arg!(matches, "silent", bool),
+        epoch: ConfigurableEpoch::from_names(
+            matches.value_of("selection").unwrap(),
+            matches.value_of("replacement").unwrap(),
+            get_arg!(matches, "tournament-size", usize),
+            get_arg!(matches, "breed-factor", f64),
+            get_arg!(matches, "survival-factor", f64),
+            get_arg!(matches, "offspring", usize),
+        )
+        .expect("Invalid epoch configuration"),
+        crossover: get_arg!(matches, "crossover", BalancedCrossover),
+        mutation: BalancedMutation::from_name(
+            matches.value_of("mutation").unwrap(),
+            get_arg!(matches, "swap-count", SwapCount),
+        )
+        .expect("Invalid mutation"),
+        rate_adaptation: get_arg!(matches, "rate-adaptation", RateAdaptation),
         fitness_f: f,
     };
     let runs = get_arg!(matches, "runs", usize);
@@ -151,6 +226,12 @@ fn main() {
         "Looking for OA[N: {}, k: {}, s: 2, t: {}]",
         params.ngrande, params.k, params.t
     );
+    if let Some(violation) = oarray::bounds::check_parameters(params.ngrande, params.k, params.t) {
+        warn!(
+            "OA[N: {}, k: {}, s: 2, t: {}] cannot exist: {}",
+            params.ngrande, params.k, params.t, violation
+        );
+    }
     debug!("{:#?}", params);
 
     let show_progress = threads == 1 && !params.silent;
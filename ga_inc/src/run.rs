@@ -2,11 +2,8 @@ use spiril::{population::Population, unit::Unit};
 //use std::cmp;
 use std::f64;
 
-//mod epoch;
-//use epoch::TournamentEpoch;
-use spiril::epoch::DefaultEpoch;
-
 use genetic_operators::IncGAOArray;
+use ga_common::{BalancedCrossover, BalancedMutation, ConfigurableEpoch, Mutator, RateAdaptation};
 use oarray::{FitnessFunction, OArray};
 use pbr::ProgressBar;
 
@@ -20,6 +17,10 @@ pub(crate) struct RunParameters {
     pub epochs: usize,
     pub fitness_f: FitnessFunction,
     pub silent: bool,
+    pub epoch: ConfigurableEpoch,
+    pub crossover: BalancedCrossover,
+    pub mutation: BalancedMutation,
+    pub rate_adaptation: RateAdaptation,
 }
 
 pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
@@ -27,7 +28,8 @@ pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
     assert!(ngrande % (2usize.pow(p.t)) == 0, "2^t non divide N");
     let mut partial = OArray::generate_partial(ngrande, p.t, p.fitness_f);
     let mut k_current = p.t as usize;
-    let epoch = DefaultEpoch::default();
+    let epoch = p.epoch;
+    let mutator = Mutator::new(p.mutation, p.rate_adaptation, p.mutation_prob);
     while k_current < p.k {
         let num_epochs = p.epochs * (k_current + 1 - p.t as usize);
         let best;
@@ -35,7 +37,13 @@ pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
         {
             let mut units: Vec<IncGAOArray> = Vec::with_capacity(p.pop_size);
             for _ in 0..p.pop_size {
-                units.push(IncGAOArray::new(&partial, p.mutation_prob, p.k));
+                units.push(IncGAOArray::new(
+                    &partial,
+                    p.mutation_prob,
+                    p.crossover,
+                    mutator.clone(),
+                    p.k,
+                ));
             }
 
             let mut pbar = ProgressBar::new(num_epochs as u64);
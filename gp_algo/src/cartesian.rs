@@ -0,0 +1,363 @@
+//! Programmazione genetica cartesiana: un solo grafo di nodi, con le primitive
+//! dell'insieme di funzioni scelto, e k uscite, una per colonna, così le colonne possono condividere
+//! sottoespressioni. Gli ingressi del nodo i sono le variabili o i nodi
+//! precedenti entro `levels_back`; i nodi non raggiungibili dalle uscite
+//! sono introni e non vengono valutati.
+
+use gp_common::{FunctionSet, Primitive};
+use gpoarray::imbalance;
+use oarray::anf::Anf;
+use oarray::binary_strings::BinaryStringIterator;
+use oarray::wtform::TruthTable;
+use oarray::{FitnessCache, FitnessFunction, OArray};
+use rand::{thread_rng, Rng};
+use spiril::unit::Unit;
+use std::fmt::{Display, Error, Formatter};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CgpParameters {
+    /// numero di nodi del grafo
+    pub nodes: usize,
+    /// quanti nodi precedenti può usare ogni nodo (0: tutti)
+    pub levels_back: usize,
+    /// probabilità di cambiare ogni gene
+    pub mutation_rate: f64,
+}
+
+/// Ogni nodo ha sempre tre ingressi; quelli oltre l'arietà sono ignorati
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CgpNode {
+    pub function: Primitive,
+    pub inputs: [usize; 3],
+}
+
+/// Gli indici da 0 a n-1 sono le variabili, da n in poi i nodi
+#[derive(Debug, Clone, PartialEq)]
+pub struct CgpGenome {
+    pub n_variables: usize,
+    pub levels_back: usize,
+    /// le costanti non sono usate: ogni nodo è una primitiva
+    pub functions: FunctionSet,
+    pub nodes: Vec<CgpNode>,
+    pub outputs: Vec<usize>,
+}
+
+impl CgpGenome {
+    pub fn new_rand(
+        n_variables: usize,
+        n_outputs: usize,
+        params: &CgpParameters,
+        functions: FunctionSet,
+        r: &mut impl Rng,
+    ) -> Self {
+        let mut out = CgpGenome {
+            n_variables,
+            levels_back: params.levels_back,
+            functions,
+            nodes: Vec::with_capacity(params.nodes),
+            outputs: Vec::with_capacity(n_outputs),
+        };
+        for i in 0..params.nodes {
+            let node = CgpNode {
+                function: functions.choose(r),
+                inputs: [
+                    out.random_source(i, r),
+                    out.random_source(i, r),
+                    out.random_source(i, r),
+                ],
+            };
+            out.nodes.push(node);
+        }
+        for _ in 0..n_outputs {
+            let o = out.random_output(r);
+            out.outputs.push(o);
+        }
+        out
+    }
+
+    /// Una variabile o uno dei nodi precedenti il nodo `node` entro `levels_back`
+    fn random_source(&self, node: usize, r: &mut impl Rng) -> usize {
+        let window = if self.levels_back == 0 {
+            node
+        } else {
+            node.min(self.levels_back)
+        };
+        let x = r.gen_range(0, self.n_variables + window);
+        if x < self.n_variables {
+            x
+        } else {
+            self.n_variables + node - window + (x - self.n_variables)
+        }
+    }
+
+    fn random_output(&self, r: &mut impl Rng) -> usize {
+        r.gen_range(0, self.n_variables + self.nodes.len())
+    }
+
+    /// Nodi raggiungibili dalle uscite
+    pub fn active(&self) -> Vec<bool> {
+        let mut active = vec![false; self.nodes.len()];
+        let n = self.n_variables;
+        for &o in &self.outputs {
+            if o >= n {
+                active[o - n] = true;
+            }
+        }
+        for i in (0..self.nodes.len()).rev() {
+            if !active[i] {
+                continue;
+            }
+            let node = &self.nodes[i];
+            for &src in &node.inputs[..node.function.arity()] {
+                if src >= n {
+                    active[src - n] = true;
+                }
+            }
+        }
+        active
+    }
+
+    /// Valori delle uscite per un assegnamento delle variabili
+    pub fn evaluate(&self, env: &[bool], active: &[bool]) -> Vec<bool> {
+        let mut values = env.to_vec();
+        values.resize(self.n_variables + self.nodes.len(), false);
+        for (i, node) in self.nodes.iter().enumerate() {
+            if active[i] {
+                let [a, b, c] = node.inputs;
+                values[self.n_variables + i] = node.function.apply(values[a], values[b], values[c]);
+            }
+        }
+        self.outputs.iter().map(|&o| values[o]).collect()
+    }
+
+    /// Mutazione puntiforme: ogni gene cambia con probabilità `rate`;
+    /// se nessuno cambia, ne cambia uno a caso
+    pub fn mutate(&mut self, rate: f64, r: &mut impl Rng) {
+        let genes = self.nodes.len() * 4 + self.outputs.len();
+        let mut mutated = false;
+        for gene in 0..genes {
+            if r.gen_bool(rate) {
+                self.mutate_gene(gene, r);
+                mutated = true;
+            }
+        }
+        if !mutated {
+            let gene = r.gen_range(0, genes);
+            self.mutate_gene(gene, r);
+        }
+    }
+
+    fn mutate_gene(&mut self, gene: usize, r: &mut impl Rng) {
+        let n_node_genes = self.nodes.len() * 4;
+        if gene >= n_node_genes {
+            self.outputs[gene - n_node_genes] = self.random_output(r);
+        } else if gene.is_multiple_of(4) {
+            self.nodes[gene / 4].function = self.functions.choose(r);
+        } else {
+            let src = self.random_source(gene / 4, r);
+            self.nodes[gene / 4].inputs[gene % 4 - 1] = src;
+        }
+    }
+
+    /// Forma normale algebrica dell'uscita `output`, dalla sua tabella di verità
+    pub fn anf(&self, output: usize) -> Anf {
+        let active = self.active();
+        let table = BinaryStringIterator::new(self.n_variables)
+            .map(|env| self.evaluate(&env, &active)[output])
+            .collect();
+        TruthTable::new(table).anf()
+    }
+}
+
+/// I nodi attivi come assegnamenti, nell'ordine in cui vengono calcolati,
+/// poi le uscite: un nodo condiviso da più uscite compare una volta sola
+impl Display for CgpGenome {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let n = self.n_variables;
+        let name = |src: usize| {
+            if src < n {
+                format!("x{}", src)
+            } else {
+                format!("n{}", src - n)
+            }
+        };
+        let active = self.active();
+        for (i, node) in self.nodes.iter().enumerate().filter(|&(i, _)| active[i]) {
+            let args: Vec<String> = node.inputs[..node.function.arity()]
+                .iter()
+                .map(|&src| name(src))
+                .collect();
+            writeln!(f, "n{} = {}({})", i, node.function.name(), args.join(", "))?;
+        }
+        for (i, &o) in self.outputs.iter().enumerate() {
+            writeln!(f, "y{} = {}", i, name(o))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct CGPOArray {
+    pub genome: CgpGenome,
+    ngrande: usize,
+    k: usize,
+    target_t: u32,
+    /// fitness già calcolate, condivise da tutta la popolazione
+    cache: Arc<FitnessCache>,
+    mutation_rate: f64,
+    fitness_f: FitnessFunction,
+}
+
+impl CGPOArray {
+    pub fn new_rand(
+        ngrande: usize,
+        k: usize,
+        target_t: u32,
+        params: &CgpParameters,
+        fitness_f: FitnessFunction,
+        functions: FunctionSet,
+        cache: Arc<FitnessCache>,
+    ) -> Self {
+        let n = (ngrande as f64).log2().ceil() as usize;
+        CGPOArray {
+            genome: CgpGenome::new_rand(n, k, params, functions, &mut thread_rng()),
+            ngrande,
+            k,
+            target_t,
+            cache,
+            mutation_rate: params.mutation_rate,
+            fitness_f,
+        }
+    }
+
+    pub fn to_oarray(&self) -> OArray {
+        let active = self.genome.active();
+        let mut oa_data = vec![false; self.ngrande * self.k];
+        let envs = BinaryStringIterator::new(self.genome.n_variables).take(self.ngrande);
+        for (row, env) in envs.enumerate() {
+            for (col, x) in self.genome.evaluate(&env, &active).into_iter().enumerate() {
+                oa_data[col * self.ngrande + row] = x;
+            }
+        }
+        OArray::new(self.ngrande, self.k, self.target_t, oa_data, self.fitness_f)
+    }
+}
+
+impl Unit for CGPOArray {
+    /// La chiave della cache è il sottografo attivo: un successo evita di
+    /// valutare il grafo, e le mutazioni degli introni lo trovano già calcolato
+    fn fitness(&self) -> f64 {
+        let active = self.genome.active();
+        let nodes: Vec<(usize, &CgpNode)> = self
+            .genome
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| active[i])
+            .collect();
+        self.cache
+            .get_or_insert_with(&(nodes, &self.genome.outputs), || {
+                let oa = self.to_oarray();
+                oa.fitness() - imbalance(&oa) as f64
+            })
+    }
+
+    /// Come d'uso nella CGP non c'è crossover: il figlio è una copia mutata
+    fn breed_with(&self, _other: &Self) -> Self {
+        let mut child = self.clone();
+        child.genome.mutate(self.mutation_rate, &mut thread_rng());
+        child
+    }
+}
+
+impl Display for CGPOArray {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let active = self.genome.active().iter().filter(|&&a| a).count();
+        writeln!(f, "Active nodes: {}/{}", active, self.genome.nodes.len())?;
+        write!(f, "{}", self.genome)?;
+        for i in 0..self.k {
+            writeln!(f, "ANF {}: {}", i, self.genome.anf(i))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_genome() {
+        let mut r = thread_rng();
+        let params = CgpParameters {
+            nodes: 30,
+            levels_back: 5,
+            mutation_rate: 0.1,
+        };
+        for _ in 0..20 {
+            let functions = "xor,and,or,not,if,nand,nor,xnor,maj,implies"
+                .parse()
+                .unwrap();
+            let mut g = CgpGenome::new_rand(3, 4, &params, functions, &mut r);
+            g.mutate(params.mutation_rate, &mut r);
+            //i nodi usano solo variabili o nodi precedenti entro levels_back
+            for (i, node) in g.nodes.iter().enumerate() {
+                assert!(node
+                    .inputs
+                    .iter()
+                    .all(|&s| s < 3 || (s - 3 < i && i - (s - 3) <= 5)));
+            }
+            //l'ANF di ogni uscita ha la tabella di verità del grafo
+            let active = g.active();
+            let tables: Vec<Vec<bool>> = (0..4).map(|i| g.anf(i).truth_table().table).collect();
+            for (row, env) in BinaryStringIterator::new(3).enumerate() {
+                let out = g.evaluate(&env, &active);
+                assert!(out.iter().enumerate().all(|(i, &x)| tables[i][row] == x));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_nodes() {
+        //x0 ^ x1 condiviso da due uscite, il nodo 1 è un introne
+        let g = CgpGenome {
+            n_variables: 2,
+            levels_back: 0,
+            functions: FunctionSet::default(),
+            nodes: vec![
+                CgpNode {
+                    function: Primitive::Xor,
+                    inputs: [0, 1, 0],
+                },
+                CgpNode {
+                    function: Primitive::And,
+                    inputs: [0, 1, 0],
+                },
+                CgpNode {
+                    function: Primitive::Not,
+                    inputs: [2, 3, 3],
+                },
+            ],
+            outputs: vec![2, 4, 0],
+        };
+        assert!(g.active() == vec![true, false, true]);
+        assert!(g.evaluate(&[true, false], &g.active()) == vec![true, false, true]);
+        assert!(g.to_string() == "n0 = xor(x0, x1)\nn2 = not(n0)\ny0 = n0\ny1 = n2\ny2 = x0\n");
+        let unit = CGPOArray {
+            genome: g,
+            ngrande: 4,
+            k: 3,
+            target_t: 2,
+            cache: Arc::new(FitnessCache::new(0)),
+            mutation_rate: 0.1,
+            fitness_f: FitnessFunction::DeltaFast,
+        };
+        let oa = unit.to_oarray();
+        assert!(
+            oa.d == vec![
+                false, true, true, false, true, false, false, true, false, true, false, true
+            ]
+        );
+    }
+}
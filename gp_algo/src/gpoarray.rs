@@ -1,10 +1,25 @@
 use evco::gp::tree::*;
 use evco::gp::*;
-use oarray::{OArray, FitnessFunction};
+use gp_common::{FunctionSet, Pivot};
+use oarray::{FitnessCache, FitnessFunction, OArray};
 use rand::Rng;
 use spiril::unit::Unit;
 use std::fmt::{Display, Error, Formatter};
-use treeformula::{TreeFormula, TreeFormulaConfig};
+use std::sync::Arc;
+use gp_common::{TreeFormula, TreeFormulaConfig};
+
+/// Impostazioni degli alberi, uguali per tutte le unità di una run
+#[derive(Clone, Copy)]
+pub struct GpParameters {
+    /// profondità massima degli alberi
+    pub max_depth: usize,
+    pub crossover: Crossover,
+    pub mutation: Mutation,
+    pub mutation_prob: f64,
+    pub functions: FunctionSet,
+    /// colonne bilanciate per costruzione
+    pub balanced: bool,
+}
 
 #[derive(Clone)]
 pub struct GPOArray<R: Rng> {
@@ -13,12 +28,16 @@ pub struct GPOArray<R: Rng> {
     n: usize,
     k: usize,
     target_t: u32,
-    lazy_fitness: Option<f64>,
+    /// fitness già calcolate, condivise da tutta la popolazione
+    cache: Arc<FitnessCache>,
     crossover: Crossover,
     mutation: Mutation,
     mutation_prob: f64,
     tree_gen: TreeGen<R>,
-    fitness_f: FitnessFunction
+    fitness_f: FitnessFunction,
+    functions: FunctionSet,
+    /// variabile in xor con ogni formula, se le colonne sono bilanciate per costruzione
+    pivots: Vec<Pivot>,
 }
 
 struct BinaryStringIterator {
@@ -54,18 +73,24 @@ impl<R: Rng + Send> GPOArray<R> {
         ngrande: usize,
         k: usize,
         target_t: u32,
-        max_depth: usize,
+        params: &GpParameters,
         rng: R,
-        crossover: Crossover,
-        mutation: Mutation,
-        mutation_prob: f64,
-        fitness_f: FitnessFunction
+        fitness_f: FitnessFunction,
+        cache: Arc<FitnessCache>,
     ) -> Self {
+        let balanced = params.balanced;
+        let functions = params.functions;
         let mut trees = Vec::with_capacity(k);
-        let mut tree_gen = TreeGen::perfect(rng, 1, max_depth);
+        let mut tree_gen = TreeGen::perfect(rng, 1, params.max_depth);
         //let ngrande = 2usize.pow(n as u32);
         let n = (ngrande as f64).log2().ceil() as usize;
-        let config = TreeFormulaConfig { n_variables: n };
+        let pivots: Vec<Pivot> = (0..k)
+            .map(|_| Pivot::new_rand(n, balanced, &mut tree_gen))
+            .collect();
+        let config = TreeFormulaConfig {
+            n_variables: pivots[0].n_variables(),
+            functions,
+        };
         for _ in 0..k {
             trees.push(Individual::new(&mut tree_gen, &config));
         }
@@ -75,51 +100,61 @@ impl<R: Rng + Send> GPOArray<R> {
             n,
             k,
             target_t,
-            lazy_fitness: None,
-            crossover,
-            mutation,
+            cache,
+            crossover: params.crossover,
+            mutation: params.mutation,
             tree_gen,
-            mutation_prob,
-            fitness_f
+            mutation_prob: params.mutation_prob,
+            fitness_f,
+            functions,
+            pivots,
         }
     }
     pub fn to_oarray(&self) -> OArray {
         let mut oa_data = Vec::with_capacity(self.ngrande * self.k);
         for col in 0..self.k {
             for env in BinaryStringIterator::new(self.n) {
-                oa_data.push(self.trees[col].tree.evaluate(&env));
+                oa_data.push(self.pivots[col].evaluate(&self.trees[col].tree, &env));
             }
         }
         OArray::new(self.ngrande, self.k, self.target_t, oa_data, self.fitness_f)
     }
     pub fn mutate(&mut self) {
         let config = TreeFormulaConfig {
-            n_variables: self.n,
+            //tutte le colonne hanno lo stesso numero di variabili
+            n_variables: self.pivots[0].n_variables(),
+            functions: self.functions,
         };
         for tree in &mut self.trees {
             self.mutation.mutate(tree, &mut self.tree_gen, &config);
         }
-        self.lazy_fitness = None;
+        //in media una variabile pivot cambia a ogni mutazione
+        for p in &mut self.pivots {
+            p.mutate(1.0 / self.k as f64, &mut self.tree_gen);
+        }
+    }
+    pub fn formulas(&self) -> Vec<TreeFormula> {
+        (0..self.k).map(|i| self.formula(i)).collect()
+    }
+    /// Formula completa della colonna `col`
+    pub fn formula(&self, col: usize) -> TreeFormula {
+        self.pivots[col].formula(&self.trees[col].tree)
     }
 }
 
 impl<R: Rng + Send + Clone> Unit for GPOArray<R> {
+    /// La chiave della cache sono le formule: un successo evita anche di valutarle
     fn fitness(&self) -> f64 {
-        let oa = self.to_oarray();
-        let oa_fit = oa.fitness();
-        let mut tot = 0i64;
-        for col in oa.iter_cols() {
-            let mut acc = 0i64;
-            for cell in col {
-                if *cell {
-                    acc += 1;
-                } else {
-                    acc -= 1;
-                }
-            }
-            tot += acc.abs();
-        }
-        oa_fit - (tot as f64)
+        let key: Vec<(&Pivot, &TreeFormula)> = self
+            .pivots
+            .iter()
+            .zip(self.trees.iter().map(|t| &*t.tree))
+            .collect();
+        self.cache.get_or_insert_with(&key, || {
+            let oa = self.to_oarray();
+            let oa_fit = oa.fitness();
+            oa_fit - (imbalance(&oa) as f64)
+        })
     }
     fn breed_with(&self, other: &Self) -> Self {
         assert!(self.n == other.n);
@@ -137,10 +172,29 @@ impl<R: Rng + Send + Clone> Unit for GPOArray<R> {
     }
 }
 
+/// Somma, su tutte le colonne, della differenza tra numero di uni e di zeri
+pub fn imbalance(oa: &OArray) -> i64 {
+    let mut tot = 0i64;
+    for col in oa.iter_cols() {
+        let mut acc = 0i64;
+        for cell in col {
+            if *cell {
+                acc += 1;
+            } else {
+                acc -= 1;
+            }
+        }
+        tot += acc.abs();
+    }
+    tot
+}
+
 impl<R: Rng + Send> Display for GPOArray<R> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        for (i, x) in self.trees.iter().enumerate() {
-            writeln!(f, "Formula {}: {}", i, x.tree)?;
+        for i in 0..self.k {
+            let formula = self.formula(i);
+            writeln!(f, "Formula {}: {}", i, formula)?;
+            writeln!(f, "ANF {}: {}", i, formula.anf(self.n))?;
         }
         writeln!(f, "")
     }
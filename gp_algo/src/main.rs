@@ -1,4 +1,6 @@
 extern crate clap;
+extern crate ga_common;
+extern crate gp_common;
 extern crate evco;
 extern crate oarray;
 extern crate pbr;
@@ -9,13 +11,15 @@ extern crate log;
 extern crate simplelog;
 
 use clap::{App, Arg};
+use ga_common::{ConfigurableEpoch, IslandParameters, Topology};
+use gp_common::{FunctionSet, Notation};
 use simplelog::*;
 use std::fs::File;
 use std::thread;
 
+mod cartesian;
 mod gpoarray;
 mod run;
-mod treeformula;
 
 use run::run;
 
@@ -30,6 +34,8 @@ macro_rules! get_arg {
 }
 
 fn main() {
+    ga_common::register_custom_fitness();
+    let fitness_help = format!("Fitness function [{}]", oarray::fitness_names().join(", "));
     let matches = App::new("GP run")
         .about("Run the Genetic Programming algorithm")
         .arg(
@@ -104,7 +110,7 @@ fn main() {
         .arg(
             Arg::with_name("fitness")
                 .long("fitness")
-                .help("Fitness function [Delta, DeltaFast, Walsh]")
+                .help(&fitness_help)
                 .default_value("DeltaFast"),
         )
         .arg(
@@ -113,20 +119,134 @@ fn main() {
                 .help("Exponent for the fitness function")
                 .default_value("2"),
         )
+        .arg(
+            Arg::with_name("cache-size")
+                .long("cache-size")
+                .help("Maximum number of cached fitness values per run (0 disables the cache)")
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::with_name("islands")
+                .long("islands")
+                .help("Number of islands (subpopulations of pop-size units, one thread each) per run")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("topology")
+                .long("topology")
+                .help("Migration topology between islands [ring, full]")
+                .default_value("ring"),
+        )
+        .arg(
+            Arg::with_name("migration-interval")
+                .long("migration-interval")
+                .help("Epochs between two migrations")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::with_name("migration-size")
+                .long("migration-size")
+                .help("Number of best units sent to each neighbouring island")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::with_name("selection")
+                .long("selection")
+                .help("Parent selection scheme [truncation, tournament, roulette, rank]")
+                .default_value("truncation"),
+        )
+        .arg(
+            Arg::with_name("tournament-size")
+                .long("tournament-size")
+                .help("Number of units competing in each tournament")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("replacement")
+                .long("replacement")
+                .help("Replacement scheme [survivors, generational, plus, comma, steady-state]")
+                .default_value("survivors"),
+        )
+        .arg(
+            Arg::with_name("offspring")
+                .long("offspring")
+                .help("Offspring per epoch for plus, comma and steady-state (0: pop-size)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("representation")
+                .long("representation")
+                .help("Genome representation [tree, cartesian]: cartesian evolves one graph with an output per column")
+                .default_value("tree"),
+        )
+        .arg(
+            Arg::with_name("cgp-nodes")
+                .long("cgp-nodes")
+                .help("Number of nodes of the Cartesian GP graph")
+                .default_value("50"),
+        )
+        .arg(
+            Arg::with_name("cgp-levels-back")
+                .long("cgp-levels-back")
+                .help("How many previous nodes each Cartesian GP node can read (0: all)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("cgp-mutation-rate")
+                .long("cgp-mutation-rate")
+                .help("Probability that each gene of the Cartesian GP genome is mutated")
+                .default_value("0.05"),
+        )
+        .arg(
+            Arg::with_name("functions")
+                .long("functions")
+                .help("Function set as name[:weight] items [xor, and, or, not, if, nand, nor, xnor, maj, implies]; const:p makes a leaf constant with probability p")
+                .default_value("xor,and,or,not,if"),
+        )
+        .arg(
+            Arg::with_name("balanced")
+                .long("balanced")
+                .help("Build every column as x_i xor a formula of the other variables, so that columns are balanced (N must be a power of 2)")
+                .default_value("false"),
+        )
+        .arg(
+            Arg::with_name("notation")
+                .long("notation")
+                .help("Notation of the logged formulas [latex, infix, rust, c, anf]")
+                .default_value("latex"),
+        )
+        .arg(
+            Arg::with_name("simplify")
+                .long("simplify")
+                .help("Simplify the logged formulas (constants, x xor x, If rewrites, introns)")
+                .default_value("true"),
+        )
         .get_matches();
 
     let ngrande = get_arg!(matches, "N", usize);
     let n = (ngrande as f64).log2().ceil() as usize;
-    let f = match matches.value_of("fitness").unwrap() {
-        "Delta" => oarray::FitnessFunction::Delta,
-        "DeltaFast" => oarray::FitnessFunction::DeltaFast,
-        "Cidev" => oarray::FitnessFunction::Cidev,
-        "SheerLuck" => oarray::FitnessFunction::SheerLuck,
-        "Walsh" => oarray::FitnessFunction::Walsh(get_arg!(matches, "fitness-exp", u32)),
-        "WalshFast" => oarray::FitnessFunction::WalshFaster(get_arg!(matches, "fitness-exp", u32)),
-        _ => panic!("Invalid function name"),
-    };
+    let f = oarray::fitness_by_name(
+        matches.value_of("fitness").unwrap(),
+        get_arg!(matches, "fitness-exp", u32),
+    )
+    .expect("Invalid function name");
 
+    let cgp = match matches.value_of("representation").unwrap() {
+        "tree" => None,
+        "cartesian" => Some(cartesian::CgpParameters {
+            nodes: get_arg!(matches, "cgp-nodes", usize),
+            levels_back: get_arg!(matches, "cgp-levels-back", usize),
+            mutation_rate: get_arg!(matches, "cgp-mutation-rate", f64),
+        }),
+        r => panic!("Invalid representation {}", r),
+    };
+    if cgp.is_some() && get_arg!(matches, "balanced", bool) {
+        clap::Error::with_description(
+            "--balanced true requires --representation tree",
+            clap::ErrorKind::ArgumentConflict,
+        )
+        .exit();
+    }
     let params = run::RunParameters {
         ngrande,
         k: get_arg!(matches, "k", usize),
@@ -135,9 +255,28 @@ fn main() {
         pop_size: get_arg!(matches, "pop-size", usize),
         max_depth: matches.value_of("max-depth").unwrap().parse().unwrap_or(n),
         mutation_prob: get_arg!(matches, "mutation-prob", f64),
-        breed_factor: get_arg!(matches, "breed-factor", f64),
-        survival_factor: get_arg!(matches, "survival-factor", f64),
+        epoch: ConfigurableEpoch::from_names(
+            matches.value_of("selection").unwrap(),
+            matches.value_of("replacement").unwrap(),
+            get_arg!(matches, "tournament-size", usize),
+            get_arg!(matches, "breed-factor", f64),
+            get_arg!(matches, "survival-factor", f64),
+            get_arg!(matches, "offspring", usize),
+        )
+        .expect("Invalid epoch configuration"),
         fitness_f: f,
+        functions: get_arg!(matches, "functions", FunctionSet),
+        balanced: get_arg!(matches, "balanced", bool),
+        notation: get_arg!(matches, "notation", Notation),
+        simplify: get_arg!(matches, "simplify", bool),
+        cache_size: get_arg!(matches, "cache-size", usize),
+        islands: IslandParameters {
+            islands: get_arg!(matches, "islands", usize),
+            topology: get_arg!(matches, "topology", Topology),
+            migration_interval: get_arg!(matches, "migration-interval", usize),
+            migration_size: get_arg!(matches, "migration-size", usize),
+        },
+        cgp,
     };
     let runs = get_arg!(matches, "runs", usize);
     let threads = get_arg!(matches, "threads", usize);
@@ -162,6 +301,12 @@ fn main() {
         "Looking for OA[N: {}, k: {}, s: 2, t: {}]",
         params.ngrande, params.k, params.t
     );
+    if let Some(violation) = oarray::bounds::check_parameters(params.ngrande, params.k, params.t) {
+        warn!(
+            "OA[N: {}, k: {}, s: 2, t: {}] cannot exist: {}",
+            params.ngrande, params.k, params.t, violation
+        );
+    }
     debug!("{:#?}", params);
 
     let show_progress = threads == 1;
@@ -5,9 +5,14 @@ use rand::OsRng;
 use spiril::population::Population;
 use spiril::unit::Unit;
 use std::f64;
-use gpoarray::GPOArray;
-use spiril::epoch::DefaultEpoch;
-use oarray::FitnessFunction;
+use std::sync::Arc;
+use cartesian::{CGPOArray, CgpParameters};
+use ga_common::{run_islands, ConfigurableEpoch, IslandParameters};
+use gpoarray::{GPOArray, GpParameters};
+use log::Level;
+use rand::Rng;
+use gp_common::{FunctionSet, Notation};
+use oarray::{FitnessCache, FitnessFunction, OArray};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct RunParameters {
@@ -17,10 +22,19 @@ pub(crate) struct RunParameters {
     pub pop_size : usize,
     pub max_depth: usize,
     pub mutation_prob: f64,
-    pub breed_factor: f64,
-    pub survival_factor: f64,
+    pub epoch: ConfigurableEpoch,
     pub epochs: usize,
-    pub fitness_f: FitnessFunction
+    pub fitness_f: FitnessFunction,
+    pub functions: FunctionSet,
+    /// colonne bilanciate per costruzione (solo GP ad albero)
+    pub balanced: bool,
+    pub notation: Notation,
+    /// semplifica le formule trovate prima di stamparle
+    pub simplify: bool,
+    pub cache_size: usize,
+    pub islands: IslandParameters,
+    /// `None` per la GP ad albero
+    pub cgp: Option<CgpParameters>,
 }
 
 
@@ -29,28 +43,99 @@ pub(crate) fn run(p : &RunParameters, show_progress: bool) -> (bool, bool) {
     let crossover = Crossover::hard_prune(p.max_depth);
     //let crossover = Crossover::one_point_leaf_biased(leaf_bias);
     let mutation = Mutation::uniform_prune(p.max_depth);
-    let epoch = DefaultEpoch::new(0.2, 0.8);
 
     //let mut rng = OsRng::new().unwrap();
     //let tree_gen = TreeGen::full(&mut rng, 1, 4);
 
+    if p.balanced {
+        assert!(p.ngrande.is_power_of_two() && p.ngrande >= 4, "N deve essere una potenza di 2 almeno 4");
+    }
     let rng = OsRng::new().unwrap();
-    let population: Vec<GPOArray<_>> = (0..p.pop_size)
+    let cache = Arc::new(FitnessCache::new(p.cache_size));
+    let n_islands = p.islands.islands.max(1);
+    if let Some(cgp) = p.cgp {
+        let population = (0..p.pop_size * n_islands)
+            .map(|_| {
+                CGPOArray::new_rand(
+                    p.ngrande,
+                    p.k,
+                    p.t,
+                    &cgp,
+                    p.fitness_f,
+                    p.functions,
+                    cache.clone(),
+                )
+            })
+            .collect();
+        return evolve(
+            p,
+            population,
+            &cache,
+            CGPOArray::to_oarray,
+            describe_graph,
+            show_progress,
+        );
+    }
+    let params = GpParameters {
+        max_depth: p.max_depth,
+        crossover,
+        mutation,
+        mutation_prob: p.mutation_prob,
+        functions: p.functions,
+        balanced: p.balanced,
+    };
+    let population: Vec<GPOArray<_>> = (0..p.pop_size * n_islands)
         .map(|_| {
             GPOArray::new_rand(
                 p.ngrande,
                 p.k,
                 p.t,
-                p.max_depth,
+                &params,
                 rng.clone(),
-                crossover,
-                mutation,
-                p.mutation_prob,
-                p.fitness_f
+                p.fitness_f,
+                cache.clone(),
             )
         })
         .collect();
+    evolve(
+        p,
+        population,
+        &cache,
+        GPOArray::to_oarray,
+        describe_trees,
+        show_progress,
+    )
+}
+
+/// Formule delle colonne, semplificate se richiesto, e la loro ANF
+fn describe_trees<R: Rng + Send>(unit: &GPOArray<R>, p: &RunParameters) -> Vec<String> {
+    let n = (p.ngrande as f64).log2().ceil() as usize;
+    let mut out = Vec::new();
+    for (i, f) in unit.formulas().iter().enumerate() {
+        let f = if p.simplify { f.simplify(n) } else { f.clone() };
+        out.push(format!("Formula {}: {}", i, f.print(p.notation, n)));
+        out.push(format!("ANF {}: {}", i, f.anf(n)));
+    }
+    out
+}
 
+/// Il grafo non si espande in alberi, che possono essere esponenzialmente
+/// più grandi: si stampano i nodi attivi e l'ANF di ogni uscita
+fn describe_graph(unit: &CGPOArray, _p: &RunParameters) -> Vec<String> {
+    vec![unit.to_string()]
+}
+
+/// Evolve la popolazione, eventualmente divisa in isole, e controlla il migliore
+fn evolve<U: Unit + Clone>(
+    p: &RunParameters,
+    mut population: Vec<U>,
+    cache: &FitnessCache,
+    to_oarray: fn(&U) -> OArray,
+    describe: fn(&U, &RunParameters) -> Vec<String>,
+    show_progress: bool,
+) -> (bool, bool) {
+    let epoch = p.epoch;
+    let n_islands = p.islands.islands.max(1);
     let mut pb = ProgressBar::new(p.epochs as u64);
     //let (tx, rx) = mpsc::channel();
     /*set_handler(move || {
@@ -58,28 +143,46 @@ pub(crate) fn run(p : &RunParameters, show_progress: bool) -> (bool, bool) {
     })
     .unwrap();*/
 
-    let f = Population::new(population)
-        .set_size(p.pop_size)
-        .register_callback(Box::new(move |i, j| {
+    let f = if n_islands > 1 {
+        let populations: Vec<Vec<U>> = (0..n_islands)
+            .map(|_| population.split_off(population.len() - p.pop_size))
+            .collect();
+        run_islands(&p.islands, p.epochs, p.pop_size, &epoch, populations, move |i, j| {
             if show_progress {
                 pb.message(&format!(" Best: {:.4}, Mean: {:.4}; iteration ", i, j));
                 (&mut pb).inc();
             }
-            if -i < f64::EPSILON {
-                return false;
-            }
-            true
-            //rx.try_recv().is_err()
-        }))
-        .epochs(p.epochs as u32, &epoch)
-        .finish();
+        })
+    } else {
+        Population::new(population)
+            .set_size(p.pop_size)
+            .register_callback(Box::new(move |i, j| {
+                if show_progress {
+                    pb.message(&format!(" Best: {:.4}, Mean: {:.4}; iteration ", i, j));
+                    (&mut pb).inc();
+                }
+                if -i < f64::EPSILON {
+                    return false;
+                }
+                true
+                //rx.try_recv().is_err()
+            }))
+            .epochs(p.epochs as u32, &epoch)
+            .finish()
+    };
     let asd = f
         .iter()
         .max_by(|&a, &b| a.fitness().partial_cmp(&b.fitness()).unwrap())
         .unwrap();
+    debug!("Fitness cache: {}", cache);
     if -asd.fitness() < f64::EPSILON {
-        debug!("{}\n{}", asd.to_oarray(), asd);
-        (true, asd.to_oarray().check_linear())
+        if log_enabled!(Level::Debug) {
+            debug!("{}", to_oarray(asd));
+            for line in describe(asd, p) {
+                debug!("{}", line);
+            }
+        }
+        (true, to_oarray(asd).check_linear())
     } else {
         (false, false)
     }
@@ -0,0 +1,255 @@
+//! Insiemi di funzioni booleane per la programmazione genetica, con i pesi
+//! con cui ogni primitiva viene scelta durante la generazione degli alberi.
+
+use rand::Rng;
+use std::fmt::{Display, Error, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    Xor,
+    And,
+    Or,
+    Not,
+    If,
+    Nand,
+    Nor,
+    Xnor,
+    /// maggioranza di tre argomenti
+    Majority,
+    /// a → b
+    Implies,
+}
+
+impl Primitive {
+    pub const ALL: [Primitive; 10] = [
+        Primitive::Xor,
+        Primitive::And,
+        Primitive::Or,
+        Primitive::Not,
+        Primitive::If,
+        Primitive::Nand,
+        Primitive::Nor,
+        Primitive::Xnor,
+        Primitive::Majority,
+        Primitive::Implies,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::Xor => "xor",
+            Primitive::And => "and",
+            Primitive::Or => "or",
+            Primitive::Not => "not",
+            Primitive::If => "if",
+            Primitive::Nand => "nand",
+            Primitive::Nor => "nor",
+            Primitive::Xnor => "xnor",
+            Primitive::Majority => "maj",
+            Primitive::Implies => "implies",
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            Primitive::Not => 1,
+            Primitive::If | Primitive::Majority => 3,
+            _ => 2,
+        }
+    }
+
+    /// Gli argomenti oltre l'arietà sono ignorati; `If(a, b, c)` vale b se a, altrimenti c
+    pub fn apply(self, a: bool, b: bool, c: bool) -> bool {
+        match self {
+            Primitive::Xor => a ^ b,
+            Primitive::And => a && b,
+            Primitive::Or => a || b,
+            Primitive::Not => !a,
+            Primitive::If => {
+                if a {
+                    b
+                } else {
+                    c
+                }
+            }
+            Primitive::Nand => !(a && b),
+            Primitive::Nor => !(a || b),
+            Primitive::Xnor => !(a ^ b),
+            Primitive::Majority => a as u8 + b as u8 + c as u8 >= 2,
+            Primitive::Implies => !a || b,
+        }
+    }
+
+    fn index(self) -> usize {
+        Primitive::ALL.iter().position(|&p| p == self).unwrap()
+    }
+}
+
+impl FromStr for Primitive {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Primitive::ALL
+            .iter()
+            .cloned()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| format!("Unknown primitive {}", s))
+    }
+}
+
+/// Pesi di generazione delle primitive (0: esclusa) e probabilità che una
+/// foglia sia una costante invece di una variabile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FunctionSet {
+    weights: [f64; 10],
+    pub constants: f64,
+}
+
+/// Probabilità delle costanti se `const` compare senza valore
+const DEFAULT_CONSTANTS: f64 = 0.1;
+
+impl Default for FunctionSet {
+    /// Xor, And, Or, Not e If con lo stesso peso, senza costanti
+    fn default() -> Self {
+        "xor,and,or,not,if".parse().unwrap()
+    }
+}
+
+impl FromStr for FunctionSet {
+    type Err = String;
+    /// Elenco separato da virgole di `nome` o `nome:peso`; `const:p` attiva
+    /// le foglie costanti con probabilità p
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut out = FunctionSet {
+            weights: [0.0; 10],
+            constants: 0.0,
+        };
+        for item in s.split(',').map(str::trim) {
+            let mut parts = item.splitn(2, ':');
+            let name = parts.next().unwrap();
+            let value = match parts.next() {
+                Some(v) => Some(
+                    v.parse::<f64>()
+                        .ok()
+                        .filter(|&v| v >= 0.0 && v.is_finite())
+                        .ok_or_else(|| format!("Invalid weight in {}", item))?,
+                ),
+                None => None,
+            };
+            if name == "const" {
+                let p = value.unwrap_or(DEFAULT_CONSTANTS);
+                if p >= 1.0 {
+                    return Err("The probability of constants must be less than 1".to_string());
+                }
+                out.constants = p;
+            } else {
+                out.weights[name.parse::<Primitive>()?.index()] = value.unwrap_or(1.0);
+            }
+        }
+        if out.weights.iter().all(|&w| w == 0.0) {
+            return Err(format!("No primitive with positive weight in {}", s));
+        }
+        Ok(out)
+    }
+}
+
+impl FunctionSet {
+    pub fn weight(&self, p: Primitive) -> f64 {
+        self.weights[p.index()]
+    }
+
+    /// Primitive con peso positivo
+    pub fn primitives(&self) -> Vec<Primitive> {
+        Primitive::ALL
+            .iter()
+            .cloned()
+            .filter(|&p| self.weight(p) > 0.0)
+            .collect()
+    }
+
+    /// Primitiva estratta con probabilità proporzionale al peso
+    pub fn choose(&self, r: &mut impl Rng) -> Primitive {
+        let mut x = r.gen_range(0.0, self.weights.iter().sum::<f64>());
+        for (&p, &w) in Primitive::ALL.iter().zip(self.weights.iter()) {
+            if x < w {
+                return p;
+            }
+            x -= w;
+        }
+        *self.primitives().last().unwrap()
+    }
+
+    /// La prossima foglia è una costante?
+    pub fn leaf_is_constant(&self, r: &mut impl Rng) -> bool {
+        self.constants > 0.0 && r.gen_bool(self.constants)
+    }
+}
+
+impl Display for FunctionSet {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let mut items: Vec<String> = self
+            .primitives()
+            .iter()
+            .map(|&p| format!("{}:{}", p.name(), self.weight(p)))
+            .collect();
+        if self.constants > 0.0 {
+            items.push(format!("const:{}", self.constants));
+        }
+        write!(f, "{}", items.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_parse() {
+        let set: FunctionSet = "xor:2, nand, maj:0.5, const:0.25".parse().unwrap();
+        assert!(set.weight(Primitive::Xor) == 2.0);
+        assert!(set.weight(Primitive::Nand) == 1.0);
+        assert!(set.weight(Primitive::Majority) == 0.5);
+        assert!(set.weight(Primitive::And) == 0.0);
+        assert!(set.constants == 0.25);
+        assert!(set.primitives() == vec![Primitive::Xor, Primitive::Nand, Primitive::Majority]);
+        assert!(set.to_string().parse::<FunctionSet>() == Ok(set));
+        assert!(FunctionSet::default().primitives().len() == 5);
+        assert!("const".parse::<FunctionSet>().is_err());
+        assert!("xor,foo".parse::<FunctionSet>().is_err());
+        assert!("xor:-1".parse::<FunctionSet>().is_err());
+        assert!("xor:inf".parse::<FunctionSet>().is_err());
+        assert!("xor,const:NaN".parse::<FunctionSet>().is_err());
+        assert!("xor,const:1".parse::<FunctionSet>().is_err());
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut r = thread_rng();
+        let set: FunctionSet = "xor:3,implies".parse().unwrap();
+        let xors = (0..4000)
+            .filter(|_| set.choose(&mut r) == Primitive::Xor)
+            .count();
+        assert!(xors > 2700 && xors < 3300);
+        assert!((0..100).all(|_| set.choose(&mut r) != Primitive::And));
+        assert!(!set.leaf_is_constant(&mut r));
+    }
+
+    #[test]
+    fn test_apply() {
+        let table = |p: Primitive| -> Vec<bool> {
+            (0..8)
+                .map(|i| p.apply(i & 4 != 0, i & 2 != 0, i & 1 != 0))
+                .collect()
+        };
+        let maj = table(Primitive::Majority);
+        assert!(maj == vec![false, false, false, true, false, true, true, true]);
+        let imp = table(Primitive::Implies);
+        assert!(imp == vec![true, true, true, true, false, false, true, true]);
+        for (&nand, &and) in table(Primitive::Nand).iter().zip(table(Primitive::And).iter()) {
+            assert!(nand != and);
+        }
+        for (&xnor, &xor) in table(Primitive::Xnor).iter().zip(table(Primitive::Xor).iter()) {
+            assert!(xnor != xor);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! Componenti della programmazione genetica condivisi da `gp_algo` e `gp_inc`.
+extern crate evco;
+extern crate oarray;
+extern crate rand;
+
+pub mod function_set;
+pub mod pivot;
+pub mod treeformula;
+pub use function_set::{FunctionSet, Primitive};
+pub use pivot::Pivot;
+pub use treeformula::{Notation, TreeFormula, TreeFormulaConfig};
@@ -0,0 +1,93 @@
+//! Colonne bilanciate per costruzione: la colonna vale x_pivot ⊕ g, dove la
+//! formula evoluta g usa solo le altre n - 1 variabili.
+
+use evco::gp::tree::Tree;
+use rand::Rng;
+use treeformula::TreeFormula;
+
+/// Variabile in xor con la formula di una colonna, se la colonna è
+/// bilanciata per costruzione
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pivot {
+    /// variabili della colonna
+    n: usize,
+    variable: Option<usize>,
+}
+
+impl Pivot {
+    /// Una variabile a caso tra le `n` se `balanced`, altrimenti nessuna
+    pub fn new_rand(n: usize, balanced: bool, r: &mut impl Rng) -> Self {
+        Pivot {
+            n,
+            variable: if balanced { Some(r.gen_range(0, n)) } else { None },
+        }
+    }
+
+    /// Numero di variabili della formula evoluta
+    pub fn n_variables(&self) -> usize {
+        match self.variable {
+            Some(_) => self.n - 1,
+            None => self.n,
+        }
+    }
+
+    /// Valore della colonna per l'assegnamento `env` delle n variabili
+    pub fn evaluate(&self, tree: &TreeFormula, env: &[bool]) -> bool {
+        match self.variable {
+            Some(p) => tree.evaluate_balanced(p, env),
+            None => tree.evaluate(&env.to_vec()),
+        }
+    }
+
+    /// Formula completa della colonna
+    pub fn formula(&self, tree: &TreeFormula) -> TreeFormula {
+        match self.variable {
+            Some(p) => tree.with_pivot(p),
+            None => tree.clone(),
+        }
+    }
+
+    /// Con probabilità `prob` sceglie un'altra variabile
+    pub fn mutate(&mut self, prob: f64, r: &mut impl Rng) {
+        if self.variable.is_some() && r.gen_bool(prob) {
+            self.variable = Some(r.gen_range(0, self.n));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use evco::gp::tree::TreeGen;
+    use evco::gp::Individual;
+    use oarray::binary_strings::BinaryStringIterator;
+    use rand::thread_rng;
+    use treeformula::TreeFormulaConfig;
+
+    #[test]
+    fn test_pivot() {
+        let mut r = thread_rng();
+        let mut tree_gen = TreeGen::perfect(thread_rng(), 1, 4);
+        for &balanced in &[false, true] {
+            let mut pivot = Pivot::new_rand(4, balanced, &mut r);
+            let config = TreeFormulaConfig {
+                n_variables: pivot.n_variables(),
+                functions: "xor,and,or,not,maj,const".parse().unwrap(),
+            };
+            assert!(config.n_variables == if balanced { 3 } else { 4 });
+            for _ in 0..20 {
+                let g = Individual::<TreeFormula>::new(&mut tree_gen, &config).tree;
+                let full = pivot.formula(&g);
+                let ones = BinaryStringIterator::new(4)
+                    .filter(|env| {
+                        assert!(full.evaluate(env) == pivot.evaluate(&g, env));
+                        pivot.evaluate(&g, env)
+                    })
+                    .count();
+                assert!(!balanced || ones == 8);
+                pivot.mutate(1.0, &mut r);
+                assert!(pivot.variable.is_some() == balanced);
+            }
+        }
+    }
+}
@@ -0,0 +1,675 @@
+use evco::gp::tree::*;
+use function_set::{FunctionSet, Primitive};
+use oarray::anf::Anf;
+use oarray::binary_strings::BinaryStringIterator;
+use oarray::wtform::TruthTable;
+use rand::Rng;
+use std::fmt::{Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeFormula {
+    Xor(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    If(
+        BoxTree<TreeFormula>,
+        BoxTree<TreeFormula>,
+        BoxTree<TreeFormula>,
+    ),
+    And(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Or(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Not(BoxTree<TreeFormula>),
+    Nand(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Nor(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Xnor(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Majority(
+        BoxTree<TreeFormula>,
+        BoxTree<TreeFormula>,
+        BoxTree<TreeFormula>,
+    ),
+    Implies(BoxTree<TreeFormula>, BoxTree<TreeFormula>),
+    Const(bool),
+    Var(usize),
+}
+
+pub struct TreeFormulaConfig {
+    pub n_variables: usize,
+    pub functions: FunctionSet,
+}
+
+use self::TreeFormula::*;
+
+/// Hash strutturale, coerente con `Eq`: permette di usare le formule come
+/// chiave della cache delle fitness
+impl Hash for TreeFormula {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Const(x) => x.hash(state),
+            Var(i) => i.hash(state),
+            _ => {
+                for c in self.children() {
+                    (**c).hash(state);
+                }
+            }
+        }
+    }
+}
+
+impl Tree for TreeFormula {
+    type Environment = Vec<bool>;
+    type Action = bool;
+    type Config = TreeFormulaConfig;
+
+    fn branch<R: Rng>(
+        tg: &mut TreeGen<R>,
+        current_depth: usize,
+        cfg: &Self::Config,
+    ) -> BoxTree<Self> {
+        let left = Self::child(tg, current_depth + 1, cfg);
+        let right = Self::child(tg, current_depth + 1, cfg);
+        match cfg.functions.choose(tg) {
+            Primitive::Xor => Xor(left, right),
+            Primitive::And => And(left, right),
+            Primitive::Or => Or(left, right),
+            Primitive::Not => Not(left),
+            Primitive::If => If(Self::child(tg, current_depth + 1, cfg), left, right),
+            Primitive::Nand => Nand(left, right),
+            Primitive::Nor => Nor(left, right),
+            Primitive::Xnor => Xnor(left, right),
+            Primitive::Majority => Majority(Self::child(tg, current_depth + 1, cfg), left, right),
+            Primitive::Implies => Implies(left, right),
+        }
+        .into()
+    }
+
+    /// Generate tree leaves: variables, or constants if the function set allows them.
+    fn leaf<R: Rng>(tg: &mut TreeGen<R>, _: usize, cfg: &Self::Config) -> BoxTree<Self> {
+        if cfg.functions.leaf_is_constant(tg) {
+            Const(tg.gen()).into()
+        } else {
+            Var(tg.gen_range(0, cfg.n_variables)).into()
+        }
+    }
+    fn count_children(&mut self) -> usize {
+        match self {
+            Xor(_, _) | And(_, _) | Or(_, _) | Nand(_, _) | Nor(_, _) | Xnor(_, _)
+            | Implies(_, _) => 2,
+            If(_, _, _) | Majority(_, _, _) => 3,
+            Not(_) => 1,
+            Const(_) | Var(_) => 0,
+        }
+    }
+
+    fn children(&self) -> Vec<&BoxTree<Self>> {
+        match self {
+            Xor(ref c1, ref c2)
+            | And(ref c1, ref c2)
+            | Or(ref c1, ref c2)
+            | Nand(ref c1, ref c2)
+            | Nor(ref c1, ref c2)
+            | Xnor(ref c1, ref c2)
+            | Implies(ref c1, ref c2) => vec![c1, c2],
+            Not(ref c1) => vec![c1],
+            If(ref c1, ref c2, ref c3) | Majority(ref c1, ref c2, ref c3) => vec![c1, c2, c3],
+            Const(_) | Var(_) => vec![],
+        }
+    }
+    fn children_mut(&mut self) -> Vec<&mut BoxTree<Self>> {
+        match self {
+            Xor(ref mut c1, ref mut c2)
+            | And(ref mut c1, ref mut c2)
+            | Or(ref mut c1, ref mut c2)
+            | Nand(ref mut c1, ref mut c2)
+            | Nor(ref mut c1, ref mut c2)
+            | Xnor(ref mut c1, ref mut c2)
+            | Implies(ref mut c1, ref mut c2) => vec![c1, c2],
+            If(ref mut c1, ref mut c2, ref mut c3)
+            | Majority(ref mut c1, ref mut c2, ref mut c3) => vec![c1, c2, c3],
+            Not(ref mut c1) => vec![c1],
+            Const(_) | Var(_) => vec![],
+        }
+    }
+
+    fn evaluate(&self, env: &Self::Environment) -> bool {
+        match self {
+            Xor(ref a, ref b) => a.evaluate(env) ^ b.evaluate(env),
+            And(ref a, ref b) => a.evaluate(env) && b.evaluate(env),
+            Or(ref a, ref b) => a.evaluate(env) || b.evaluate(env),
+            Not(ref a) => !a.evaluate(env),
+            Nand(ref a, ref b) => !(a.evaluate(env) && b.evaluate(env)),
+            Nor(ref a, ref b) => !(a.evaluate(env) || b.evaluate(env)),
+            Xnor(ref a, ref b) => a.evaluate(env) == b.evaluate(env),
+            Majority(ref a, ref b, ref c) => {
+                let (a, b, c) = (a.evaluate(env), b.evaluate(env), c.evaluate(env));
+                a as u8 + b as u8 + c as u8 >= 2
+            }
+            Implies(ref a, ref b) => !a.evaluate(env) || b.evaluate(env),
+            Const(x) => *x,
+            Var(i) => env[*i],
+            If(ref cond, ref a, ref b) => {
+                if cond.evaluate(env) {
+                    a.evaluate(env)
+                } else {
+                    b.evaluate(env)
+                }
+            }
+        }
+    }
+}
+
+impl TreeFormula {
+    /// Forma normale algebrica della formula, valutata su `n_variables` variabili
+    pub fn anf(&self, n_variables: usize) -> Anf {
+        let table = BinaryStringIterator::new(n_variables)
+            .map(|env| self.evaluate(&env))
+            .collect();
+        TruthTable::new(table).anf()
+    }
+
+    /// Colonna bilanciata per costruzione: x_pivot ⊕ g, dove la formula g
+    /// usa solo le altre variabili (rinumerate saltando `pivot`). Su tutte
+    /// le 2^n righe vale 1 esattamente metà delle volte, qualunque sia g.
+    pub fn evaluate_balanced(&self, pivot: usize, env: &[bool]) -> bool {
+        let mut rest = env.to_vec();
+        let x = rest.remove(pivot);
+        x ^ self.evaluate(&rest)
+    }
+
+    /// La formula x_pivot ⊕ g sulle n variabili, per stamparla e calcolarne l'ANF
+    pub fn with_pivot(&self, pivot: usize) -> TreeFormula {
+        let g = self.map_vars(&|i| if i >= pivot { i + 1 } else { i });
+        Xor(Var(pivot).into(), g.into())
+    }
+
+    fn map_vars(&self, f: &impl Fn(usize) -> usize) -> TreeFormula {
+        let m = |t: &BoxTree<TreeFormula>| -> BoxTree<TreeFormula> { t.map_vars(f).into() };
+        match self {
+            Xor(ref a, ref b) => Xor(m(a), m(b)),
+            And(ref a, ref b) => And(m(a), m(b)),
+            Or(ref a, ref b) => Or(m(a), m(b)),
+            Not(ref a) => Not(m(a)),
+            If(ref c, ref a, ref b) => If(m(c), m(a), m(b)),
+            Nand(ref a, ref b) => Nand(m(a), m(b)),
+            Nor(ref a, ref b) => Nor(m(a), m(b)),
+            Xnor(ref a, ref b) => Xnor(m(a), m(b)),
+            Majority(ref a, ref b, ref c) => Majority(m(a), m(b), m(c)),
+            Implies(ref a, ref b) => Implies(m(a), m(b)),
+            Const(x) => Const(*x),
+            Var(i) => Var(f(*i)),
+        }
+    }
+}
+
+/// Notazioni con cui stampare una formula
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// quella di `Display`
+    Latex,
+    /// infissa con i simboli della logica
+    Infix,
+    /// espressione Rust sull'array di booleani `x`
+    Rust,
+    /// espressione C sull'array di interi 0/1 `x`
+    C,
+    /// forma normale algebrica
+    Anf,
+}
+
+impl FromStr for Notation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "latex" => Ok(Notation::Latex),
+            "infix" => Ok(Notation::Infix),
+            "rust" => Ok(Notation::Rust),
+            "c" => Ok(Notation::C),
+            "anf" => Ok(Notation::Anf),
+            _ => Err(format!("Unknown notation {}", s)),
+        }
+    }
+}
+
+fn konst(t: &TreeFormula) -> Option<bool> {
+    match t {
+        Const(x) => Some(*x),
+        _ => None,
+    }
+}
+
+/// a = ¬b oppure b = ¬a
+fn complementary(a: &TreeFormula, b: &TreeFormula) -> bool {
+    match (a, b) {
+        (Not(ref x), _) if **x == *b => true,
+        (_, Not(ref x)) if **x == *a => true,
+        _ => false,
+    }
+}
+
+/// Raccoglie i termini di una catena di xor; negazioni e costanti
+/// finiscono nella parità
+fn collect_xor(t: &TreeFormula, terms: &mut Vec<TreeFormula>, parity: &mut bool) {
+    match t {
+        Xor(ref a, ref b) => {
+            collect_xor(a, terms, parity);
+            collect_xor(b, terms, parity);
+        }
+        Xnor(ref a, ref b) => {
+            *parity = !*parity;
+            collect_xor(a, terms, parity);
+            collect_xor(b, terms, parity);
+        }
+        Not(ref a) => {
+            *parity = !*parity;
+            collect_xor(a, terms, parity);
+        }
+        Const(x) => *parity ^= *x,
+        t => match terms.iter().position(|x| x == t) {
+            //x ⊕ x = 0
+            Some(i) => {
+                terms.remove(i);
+            }
+            None => terms.push(t.clone()),
+        },
+    }
+}
+
+fn xor_chain(t: &TreeFormula) -> TreeFormula {
+    let mut terms = Vec::new();
+    let mut parity = false;
+    collect_xor(t, &mut terms, &mut parity);
+    let last = terms.pop();
+    let rest = terms
+        .into_iter()
+        .fold(None, |acc: Option<TreeFormula>, x| match acc {
+            Some(acc) => Some(Xor(acc.into(), x.into())),
+            None => Some(x),
+        });
+    match (rest, last, parity) {
+        (_, None, p) => Const(p),
+        (None, Some(x), false) => x,
+        (None, Some(x), true) => Not(x.into()),
+        (Some(r), Some(x), false) => Xor(r.into(), x.into()),
+        (Some(r), Some(x), true) => Xnor(r.into(), x.into()),
+    }
+}
+
+/// Semplifica la radice, supponendo i figli già semplificati
+fn simplify_node(t: TreeFormula) -> TreeFormula {
+    match t {
+        Xor(_, _) | Xnor(_, _) => xor_chain(&t),
+        Not(ref a) => match **a {
+            Not(ref b) => (**b).clone(),
+            Const(x) => Const(!x),
+            Xor(_, _) | Xnor(_, _) => xor_chain(&t),
+            _ => t.clone(),
+        },
+        And(ref a, ref b) => match (konst(a), konst(b)) {
+            (Some(false), _) | (_, Some(false)) => Const(false),
+            (Some(true), _) => (**b).clone(),
+            (_, Some(true)) => (**a).clone(),
+            _ if a == b => (**a).clone(),
+            _ if complementary(a, b) => Const(false),
+            _ => t.clone(),
+        },
+        Or(ref a, ref b) => match (konst(a), konst(b)) {
+            (Some(true), _) | (_, Some(true)) => Const(true),
+            (Some(false), _) => (**b).clone(),
+            (_, Some(false)) => (**a).clone(),
+            _ if a == b => (**a).clone(),
+            _ if complementary(a, b) => Const(true),
+            _ => t.clone(),
+        },
+        Nand(ref a, ref b) => match simplify_node(And(a.clone(), b.clone())) {
+            And(_, _) => t.clone(),
+            s => simplify_node(Not(s.into())),
+        },
+        Nor(ref a, ref b) => match simplify_node(Or(a.clone(), b.clone())) {
+            Or(_, _) => t.clone(),
+            s => simplify_node(Not(s.into())),
+        },
+        Implies(ref a, ref b) => match (konst(a), konst(b)) {
+            (Some(false), _) | (_, Some(true)) => Const(true),
+            (Some(true), _) => (**b).clone(),
+            (_, Some(false)) => simplify_node(Not(a.clone())),
+            _ if a == b => Const(true),
+            _ if complementary(a, b) => (**b).clone(),
+            _ => t.clone(),
+        },
+        If(ref c, ref a, ref b) => match (konst(c), konst(a), konst(b)) {
+            (Some(true), _, _) => (**a).clone(),
+            (Some(false), _, _) => (**b).clone(),
+            _ if a == b => (**a).clone(),
+            (_, Some(true), Some(false)) => (**c).clone(),
+            (_, Some(false), Some(true)) => simplify_node(Not(c.clone())),
+            (_, _, Some(false)) => simplify_node(And(c.clone(), a.clone())),
+            (_, _, Some(true)) => {
+                simplify_node(Or(simplify_node(Not(c.clone())).into(), a.clone()))
+            }
+            (_, Some(false), _) => {
+                simplify_node(And(simplify_node(Not(c.clone())).into(), b.clone()))
+            }
+            (_, Some(true), _) => simplify_node(Or(c.clone(), b.clone())),
+            _ if a == c => simplify_node(Or(c.clone(), b.clone())),
+            _ if b == c => simplify_node(And(c.clone(), a.clone())),
+            //c ? ¬b : b = c ⊕ b
+            _ if complementary(a, b) => xor_chain(&Xor(c.clone(), b.clone())),
+            _ => t.clone(),
+        },
+        Majority(ref a, ref b, ref c) => {
+            //ogni argomento con gli altri due, nell'ordine originale
+            let triples = [(a, b, c), (b, a, c), (c, a, b)];
+            for &(arg, x, y) in &triples {
+                match konst(arg) {
+                    Some(true) => return simplify_node(Or(x.clone(), y.clone())),
+                    Some(false) => return simplify_node(And(x.clone(), y.clone())),
+                    None => {}
+                }
+                if x == y {
+                    return (**x).clone();
+                }
+                if complementary(x, y) {
+                    return (**arg).clone();
+                }
+            }
+            t.clone()
+        }
+        Const(_) | Var(_) => t,
+    }
+}
+
+impl TreeFormula {
+    /// Semplificazione algebrica (costanti, x ⊕ x, doppie negazioni, If
+    /// riscritti come And/Or/Xor) seguita dalla rimozione degli introni:
+    /// i sottoalberi che si possono sostituire con una costante o con un
+    /// loro figlio senza cambiare la tabella di verità su `n_variables`
+    /// variabili
+    pub fn simplify(&self, n_variables: usize) -> TreeFormula {
+        let table = self.truth_table(n_variables);
+        let mut out = self.simplify_algebraic();
+        while let Some(smaller) = out.remove_intron(&table, n_variables) {
+            out = smaller.simplify_algebraic();
+        }
+        out
+    }
+
+    fn simplify_algebraic(&self) -> TreeFormula {
+        let children = self
+            .children()
+            .iter()
+            .map(|c| c.simplify_algebraic())
+            .collect();
+        simplify_node(self.rebuild(children))
+    }
+
+    fn truth_table(&self, n_variables: usize) -> Vec<bool> {
+        BinaryStringIterator::new(n_variables)
+            .map(|env| self.evaluate(&env))
+            .collect()
+    }
+
+    /// Numero di nodi
+    pub fn size(&self) -> usize {
+        1 + self.children().iter().map(|c| c.size()).sum::<usize>()
+    }
+
+    /// Lo stesso nodo con altri figli
+    fn rebuild(&self, children: Vec<TreeFormula>) -> TreeFormula {
+        let mut children = children.into_iter().map(BoxTree::from);
+        let mut next = || children.next().unwrap();
+        match self {
+            Xor(_, _) => Xor(next(), next()),
+            And(_, _) => And(next(), next()),
+            Or(_, _) => Or(next(), next()),
+            Not(_) => Not(next()),
+            If(_, _, _) => If(next(), next(), next()),
+            Nand(_, _) => Nand(next(), next()),
+            Nor(_, _) => Nor(next(), next()),
+            Xnor(_, _) => Xnor(next(), next()),
+            Majority(_, _, _) => Majority(next(), next(), next()),
+            Implies(_, _) => Implies(next(), next()),
+            Const(x) => Const(*x),
+            Var(i) => Var(*i),
+        }
+    }
+
+    /// Sottoalbero in posizione `index`, in preordine
+    fn subtree(&self, index: usize) -> &TreeFormula {
+        if index == 0 {
+            return self;
+        }
+        let mut offset = 1;
+        for c in self.children() {
+            let size = c.size();
+            if index < offset + size {
+                return c.subtree(index - offset);
+            }
+            offset += size;
+        }
+        unreachable!()
+    }
+
+    fn replace(&self, index: usize, new: &TreeFormula) -> TreeFormula {
+        if index == 0 {
+            return new.clone();
+        }
+        let mut offset = 1;
+        let children = self
+            .children()
+            .iter()
+            .map(|c| {
+                let size = c.size();
+                let out = if index >= offset && index < offset + size {
+                    c.replace(index - offset, new)
+                } else {
+                    (***c).clone()
+                };
+                offset += size;
+                out
+            })
+            .collect();
+        self.rebuild(children)
+    }
+
+    fn remove_intron(&self, table: &[bool], n_variables: usize) -> Option<TreeFormula> {
+        for index in 0..self.size() {
+            let sub = self.subtree(index);
+            let mut candidates = vec![Const(false), Const(true)];
+            candidates.extend(sub.children().iter().map(|c| (***c).clone()));
+            for candidate in candidates.iter().filter(|c| c.size() < sub.size()) {
+                let replaced = self.replace(index, candidate);
+                if replaced.truth_table(n_variables) == table {
+                    return Some(replaced);
+                }
+            }
+        }
+        None
+    }
+
+    /// La formula nella notazione scelta; `n_variables` serve solo all'ANF
+    pub fn print(&self, notation: Notation, n_variables: usize) -> String {
+        match notation {
+            Notation::Latex => self.to_string(),
+            Notation::Anf => self.anf(n_variables).to_string(),
+            _ => self.expression(notation),
+        }
+    }
+
+    fn expression(&self, notation: Notation) -> String {
+        let e = |t: &BoxTree<TreeFormula>| t.expression(notation);
+        let (xor, and, or, not) = match notation {
+            Notation::Infix => ("⊕", "∧", "∨", "¬"),
+            Notation::Rust => ("^", "&", "|", "!"),
+            _ => ("^", "&&", "||", "!"),
+        };
+        let infix = notation == Notation::Infix;
+        match self {
+            Xor(ref a, ref b) => format!("({} {} {})", e(a), xor, e(b)),
+            And(ref a, ref b) => format!("({} {} {})", e(a), and, e(b)),
+            Or(ref a, ref b) => format!("({} {} {})", e(a), or, e(b)),
+            Not(ref a) => format!("{}{}", not, e(a)),
+            Nand(ref a, ref b) if infix => format!("({} ↑ {})", e(a), e(b)),
+            Nand(ref a, ref b) => format!("{}({} {} {})", not, e(a), and, e(b)),
+            Nor(ref a, ref b) if infix => format!("({} ↓ {})", e(a), e(b)),
+            Nor(ref a, ref b) => format!("{}({} {} {})", not, e(a), or, e(b)),
+            Xnor(ref a, ref b) if infix => format!("({} ↔ {})", e(a), e(b)),
+            Xnor(ref a, ref b) => format!("({} == {})", e(a), e(b)),
+            Implies(ref a, ref b) if infix => format!("({} → {})", e(a), e(b)),
+            Implies(ref a, ref b) => format!("({}{} {} {})", not, e(a), or, e(b)),
+            If(ref c, ref a, ref b) if notation == Notation::Rust => {
+                format!("(if {} {{ {} }} else {{ {} }})", e(c), e(a), e(b))
+            }
+            If(ref c, ref a, ref b) => format!("({} ? {} : {})", e(c), e(a), e(b)),
+            Majority(ref a, ref b, ref c) if infix => {
+                format!("maj({}, {}, {})", e(a), e(b), e(c))
+            }
+            Majority(ref a, ref b, ref c) => {
+                let (a, b, c) = (e(a), e(b), e(c));
+                format!(
+                    "(({a} {and} {b}) {or} ({a} {and} {c}) {or} ({b} {and} {c}))",
+                    a = a,
+                    b = b,
+                    c = c,
+                    and = and,
+                    or = or
+                )
+            }
+            Const(x) if notation == Notation::Rust => x.to_string(),
+            Const(x) => (*x as u8).to_string(),
+            Var(i) if infix => format!("x{}", i),
+            Var(i) => format!("x[{}]", i),
+        }
+    }
+}
+
+impl Display for TreeFormula {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Xor(ref a, ref b) => write!(f, "\\left({} \\oplus {}\\right)", a, b),
+            And(ref a, ref b) => write!(f, "\\left({} \\land {}\\right)", a, b),
+            Or(ref a, ref b) => write!(f, "\\left({} \\lor {}\\right)", a, b),
+            Not(ref a) => write!(f, "\\neg {}", a),
+            Nand(ref a, ref b) => write!(f, "\\left({} \\uparrow {}\\right)", a, b),
+            Nor(ref a, ref b) => write!(f, "\\left({} \\downarrow {}\\right)", a, b),
+            Xnor(ref a, ref b) => write!(f, "\\left({} \\leftrightarrow {}\\right)", a, b),
+            Majority(ref a, ref b, ref c) => {
+                write!(f, "\\operatorname{{maj}}\\left({}, {}, {}\\right)", a, b, c)
+            }
+            Implies(ref a, ref b) => write!(f, "\\left({} \\rightarrow {}\\right)", a, b),
+            Const(x) => write!(f, "{}", *x as u8),
+            If(ref cond, ref a, ref b) => write!(
+                f,
+                "\\begin{{cases}}{} & \\text{{if }} {} \\\\ {} & \\text{{otherwise}}\\end{{cases}}",
+                a, cond, b
+            ),
+            //Val(t) => write!(f, "{}", t.to_usize().unwrap()),
+            Var(i) => write!(f, "x_{}", i),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use evco::gp::Individual;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_balanced() {
+        let mut tree_gen = TreeGen::perfect(thread_rng(), 1, 4);
+        let config = TreeFormulaConfig {
+            n_variables: 3,
+            functions: "xor,and,or,not,if,nand,maj,implies,const".parse().unwrap(),
+        };
+        for _ in 0..50 {
+            let g = Individual::<TreeFormula>::new(&mut tree_gen, &config).tree;
+            for pivot in 0..4 {
+                let full = g.with_pivot(pivot);
+                let ones = BinaryStringIterator::new(4)
+                    .filter(|env| {
+                        assert!(full.evaluate(env) == g.evaluate_balanced(pivot, env));
+                        g.evaluate_balanced(pivot, env)
+                    })
+                    .count();
+                assert!(ones == 8);
+            }
+        }
+    }
+
+    fn x(i: usize) -> BoxTree<TreeFormula> {
+        Var(i).into()
+    }
+
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |t: &TreeFormula| {
+            let mut h = DefaultHasher::new();
+            t.hash(&mut h);
+            h.finish()
+        };
+        let a = And(x(0), Xor(x(1), x(2)).into());
+        assert!(hash(&a) == hash(&a.clone()));
+        assert!(hash(&a) != hash(&Or(x(0), Xor(x(1), x(2)).into())));
+        assert!(hash(&a) != hash(&And(x(0), Xor(x(2), x(1)).into())));
+        assert!(hash(&Var(0)) != hash(&Const(false)));
+    }
+
+    #[test]
+    fn test_simplify() {
+        let not_not = Not(Not(x(0)).into());
+        assert!(not_not.simplify(2) == Var(0));
+        //x1 ⊕ x0 ⊕ x1 = x0
+        let cancel = Xor(Xor(x(1), x(0)).into(), x(1));
+        assert!(cancel.simplify(2) == Var(0));
+        //¬x0 ⊕ x1 = x0 ↔ x1
+        assert!(Xor(Not(x(0)).into(), x(1)).simplify(2) == Xnor(x(0), x(1)));
+        //If con rami uguali e If(c, a, 0)
+        assert!(If(x(0), x(1), x(1)).simplify(2) == Var(1));
+        assert!(If(x(0), x(1), Const(false).into()).simplify(2) == And(x(0), x(1)));
+        assert!(If(x(0), Not(x(1)).into(), x(1)).simplify(2) == Xor(x(0), x(1)));
+        assert!(Majority(x(0), Const(true).into(), x(1)).simplify(2) == Or(x(0), x(1)));
+        //introne: x2 ∧ ¬x2 nasconde il resto
+        let intron = Or(x(0), And(x(2), And(x(1), Not(x(2)).into()).into()).into());
+        assert!(intron.simplify(3) == Var(0));
+        //(x0 ∧ x1) ∨ x0 = x0, solo semanticamente
+        assert!(Or(And(x(0), x(1)).into(), x(0)).simplify(2) == Var(0));
+    }
+
+    #[test]
+    fn test_simplify_random() {
+        let mut tree_gen = TreeGen::perfect(thread_rng(), 1, 5);
+        let config = TreeFormulaConfig {
+            n_variables: 3,
+            functions: "xor,and,or,not,if,nand,nor,xnor,maj,implies,const:0.2"
+                .parse()
+                .unwrap(),
+        };
+        for _ in 0..100 {
+            let f = Individual::<TreeFormula>::new(&mut tree_gen, &config).tree;
+            let s = f.simplify(3);
+            assert!(s.truth_table(3) == f.truth_table(3));
+            assert!(s.size() <= f.size());
+            assert!(s.simplify(3) == s);
+        }
+    }
+
+    #[test]
+    fn test_print() {
+        let f = If(x(0), Nand(x(1), Const(true).into()).into(), Majority(x(0), x(1), x(2)).into());
+        assert!(f.print(Notation::Infix, 3) == "(x0 ? (x1 ↑ 1) : maj(x0, x1, x2))");
+        assert!(
+            f.print(Notation::Rust, 3)
+                == "(if x[0] { !(x[1] & true) } else { ((x[0] & x[1]) | (x[0] & x[2]) | (x[1] & x[2])) })"
+        );
+        assert!(
+            f.print(Notation::C, 3)
+                == "(x[0] ? !(x[1] && 1) : ((x[0] && x[1]) || (x[0] && x[2]) || (x[1] && x[2])))"
+        );
+        assert!(f.print(Notation::Anf, 3) == f.anf(3).to_string());
+        assert!(f.print(Notation::Latex, 3) == f.to_string());
+        assert!("c".parse::<Notation>() == Ok(Notation::C));
+        assert!("lisp".parse::<Notation>().is_err());
+    }
+}
@@ -1,15 +1,29 @@
 use evco::gp::tree::*;
 use evco::gp::*;
+use gp_common::{FunctionSet, Pivot};
 use oarray::binary_strings::BinaryStringIterator;
-use oarray::wtform::PolarTruthTable;
 use oarray::{
+    Fitness, FitnessCache,
     FitnessFunction::{self, *},
     OArray,
 };
 use rand::Rng;
 use spiril::unit::Unit;
 use std::fmt::{Display, Error, Formatter};
-use treeformula::{TreeFormula, TreeFormulaConfig};
+use gp_common::{TreeFormula, TreeFormulaConfig};
+
+/// Impostazioni degli alberi, uguali per tutte le unità di una run
+#[derive(Clone, Copy)]
+pub struct GpParameters {
+    /// profondità massima degli alberi
+    pub max_depth: usize,
+    pub crossover: Crossover,
+    pub mutation: Mutation,
+    pub mutation_prob: f64,
+    pub functions: FunctionSet,
+    /// colonne bilanciate per costruzione
+    pub balanced: bool,
+}
 
 #[derive(Clone)]
 pub struct IncGPOArray<'a, R: Rng> {
@@ -19,30 +33,37 @@ pub struct IncGPOArray<'a, R: Rng> {
     n: usize,
     k: usize,
     target_t: u32,
-    lazy_fitness: Option<f64>,
+    /// fitness già calcolate, condivise dalla popolazione della colonna corrente
+    cache: &'a FitnessCache,
     crossover: Crossover,
     mutation: Mutation,
     mutation_prob: f64,
     tree_gen: TreeGen<R>,
     fitness_f: FitnessFunction,
+    functions: FunctionSet,
+    /// variabile in xor con la formula, se la colonna è bilanciata per costruzione
+    pivot: Pivot,
 }
 
 impl<'a, R: Rng + Send> IncGPOArray<'a, R> {
+    /// Unità che aggiunge una colonna a `partial`, con la sua stessa fitness
     pub fn new_rand(
         partial: &'a OArray,
-        ngrande: usize,
         k: usize,
-        target_t: u32,
-        max_depth: usize,
+        params: &GpParameters,
         rng: R,
-        crossover: Crossover,
-        mutation: Mutation,
-        mutation_prob: f64,
-        fitness_f: FitnessFunction,
+        cache: &'a FitnessCache,
     ) -> Self {
-        let mut tree_gen = TreeGen::perfect(rng, 1, max_depth);
+        let ngrande = partial.ngrande;
+        let balanced = params.balanced;
+        let functions = params.functions;
+        let mut tree_gen = TreeGen::perfect(rng, 1, params.max_depth);
         let n = (ngrande as f64).log2().ceil() as usize;
-        let config = TreeFormulaConfig { n_variables: n };
+        let pivot = Pivot::new_rand(n, balanced, &mut tree_gen);
+        let config = TreeFormulaConfig {
+            n_variables: pivot.n_variables(),
+            functions,
+        };
         let tree = Individual::new(&mut tree_gen, &config);
         IncGPOArray {
             partial,
@@ -50,13 +71,15 @@ impl<'a, R: Rng + Send> IncGPOArray<'a, R> {
             ngrande,
             n,
             k,
-            target_t,
-            lazy_fitness: None,
-            crossover,
-            mutation,
+            target_t: partial.target_t,
+            cache,
+            crossover: params.crossover,
+            mutation: params.mutation,
             tree_gen,
-            mutation_prob,
-            fitness_f,
+            mutation_prob: params.mutation_prob,
+            fitness_f: partial.fitness_f,
+            functions,
+            pivot,
         }
     }
     pub fn to_oarray(&self) -> OArray {
@@ -65,55 +88,63 @@ impl<'a, R: Rng + Send> IncGPOArray<'a, R> {
         oa.d.extend(
             BinaryStringIterator::new(self.n)
                 .take(self.ngrande)
-                .map(|env| self.tree.tree.evaluate(&env)),
+                .map(|env| self.evaluate(&env)),
         );
         oa
     }
+    fn evaluate(&self, env: &[bool]) -> bool {
+        self.pivot.evaluate(&self.tree.tree, env)
+    }
+    /// Formula completa della colonna
+    pub fn formula(&self) -> TreeFormula {
+        self.pivot.formula(&self.tree.tree)
+    }
     pub fn mutate(&mut self) {
         let config = TreeFormulaConfig {
-            n_variables: self.n,
+            n_variables: self.pivot.n_variables(),
+            functions: self.functions,
         };
         self.mutation
             .mutate(&mut self.tree, &mut self.tree_gen, &config);
-        self.lazy_fitness = None;
+        self.pivot.mutate(1.0 / self.n as f64, &mut self.tree_gen);
     }
     fn oa_fitness(&self, last_col: &[bool]) -> f64 {
         match self.partial.fitness_f {
-            Walsh(x) => self.partial.walsh_incremental(x, last_col),
-            WalshFaster(x) => self.partial.walsh_incremental_faster(x, last_col),
             Comb(x) => {
                 let a = -self.partial.walsh_incremental_faster(2, last_col);
                 let oa = self.to_oarray();
-                let b = PolarTruthTable::from(&oa.truth_table()).walsh_tform().nonlinearity();
+                let b = oa.walsh_tform().nonlinearity();
                 //dbg!(b);
                 -(a + f64::max(0.0, f64::from(x) * (self.k as f64 -1.0)-1.0-b))
             }
-            DeltaFast => self.to_oarray().delta_incremental_faster(),
-            _ => self.to_oarray().fitness(),
+            f => f.incremental_fitness(self.partial, last_col),
         }
     }
 }
 
 impl<'a, R: Rng + Send + Clone> Unit for IncGPOArray<'a, R> {
+    /// La chiave della cache è la formula: un successo evita anche di valutarla
     fn fitness(&self) -> f64 {
-        let last_col: Vec<bool> = BinaryStringIterator::new(self.n)
-            .take(self.ngrande)
-            .map(|env| self.tree.tree.evaluate(&env))
-            .collect();
-        let oa_fit = self.oa_fitness(&last_col);
-        match self.partial.fitness_f {
-            Walsh(_) | WalshFaster(_) => return oa_fit,
-            _ => {}
-        }
-        let mut acc = 0i64;
-        for cell in last_col {
-            if cell {
-                acc += 1;
-            } else {
-                acc -= 1;
+        self.cache.get_or_insert_with(&(&self.pivot, &*self.tree.tree), || {
+            let last_col: Vec<bool> = BinaryStringIterator::new(self.n)
+                .take(self.ngrande)
+                .map(|env| self.evaluate(&env))
+                .collect();
+            let oa_fit = self.oa_fitness(&last_col);
+            match self.partial.fitness_f {
+                Walsh(_) | WalshFaster(_) => return oa_fit,
+                _ => {}
             }
-        }
-        oa_fit - (acc.abs() as f64)
+            let mut acc = 0i64;
+            for &cell in &last_col {
+                if cell {
+                    acc += 1;
+                } else {
+                    acc -= 1;
+                }
+            }
+            oa_fit - (acc.abs() as f64)
+        })
     }
     fn breed_with(&self, other: &Self) -> Self {
         assert!(self.n == other.n);
@@ -132,7 +163,7 @@ impl<'a, R: Rng + Send + Clone> Unit for IncGPOArray<'a, R> {
 
 impl<'a, R: Rng + Send> Display for IncGPOArray<'a, R> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        writeln!(f, "Formula: {}", self.tree)?;
+        writeln!(f, "Formula: {}", self.formula())?;
         writeln!(f, "")
     }
 }
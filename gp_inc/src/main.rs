@@ -1,5 +1,7 @@
 extern crate clap;
 extern crate evco;
+extern crate ga_common;
+extern crate gp_common;
 extern crate oarray;
 extern crate pbr;
 extern crate rand;
@@ -10,15 +12,15 @@ extern crate simplelog;
 extern crate streaming_iterator;
 
 use clap::{App, Arg};
+use ga_common::ConfigurableEpoch;
+use gp_common::{FunctionSet, Notation};
 use simplelog::*;
 use std::fs::File;
 use std::thread;
 
 mod gpoarray;
 mod run;
-mod treeformula;
 use run::run;
-mod epoch;
 
 macro_rules! get_arg {
     ($matches: expr, $x:expr, $type: ident) => {
@@ -31,6 +33,8 @@ macro_rules! get_arg {
 }
 
 fn main() {
+    ga_common::register_custom_fitness();
+    let fitness_help = format!("Fitness function [{}]", oarray::fitness_names().join(", "));
     let matches = App::new("GA run")
         .about("Run the Genetic Algorithm")
         .arg(
@@ -93,7 +97,7 @@ fn main() {
         .arg(
             Arg::with_name("fitness")
                 .long("fitness")
-                .help("Fitness function [Delta, DeltaFast, Walsh]")
+                .help(&fitness_help)
                 .default_value("DeltaFast"),
         )
         .arg(
@@ -120,18 +124,67 @@ fn main() {
                 .help("Fractions of individuals who will survive to the next epoch")
                 .default_value("0.8"),
         )
+        .arg(
+            Arg::with_name("cache-size")
+                .long("cache-size")
+                .help("Maximum number of cached fitness values per run (0 disables the cache)")
+                .default_value("20000"),
+        )
+        .arg(
+            Arg::with_name("selection")
+                .long("selection")
+                .help("Parent selection scheme [truncation, tournament, roulette, rank]")
+                .default_value("tournament"),
+        )
+        .arg(
+            Arg::with_name("tournament-size")
+                .long("tournament-size")
+                .help("Number of units competing in each tournament")
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("replacement")
+                .long("replacement")
+                .help("Replacement scheme [survivors, generational, plus, comma, steady-state]")
+                .default_value("generational"),
+        )
+        .arg(
+            Arg::with_name("offspring")
+                .long("offspring")
+                .help("Offspring per epoch for plus, comma and steady-state (0: pop-size)")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("functions")
+                .long("functions")
+                .help("Function set as name[:weight] items [xor, and, or, not, if, nand, nor, xnor, maj, implies]; const:p makes a leaf constant with probability p")
+                .default_value("xor,and,or,not,if"),
+        )
+        .arg(
+            Arg::with_name("balanced")
+                .long("balanced")
+                .help("Build every column as x_i xor a formula of the other variables, so that columns are balanced (N must be a power of 2)")
+                .default_value("false"),
+        )
+        .arg(
+            Arg::with_name("notation")
+                .long("notation")
+                .help("Notation of the logged formulas [latex, infix, rust, c, anf]")
+                .default_value("latex"),
+        )
+        .arg(
+            Arg::with_name("simplify")
+                .long("simplify")
+                .help("Simplify the logged formulas (constants, x xor x, If rewrites, introns)")
+                .default_value("true"),
+        )
         .get_matches();
 
-    let f = match matches.value_of("fitness").unwrap() {
-        "Delta" => oarray::FitnessFunction::Delta,
-        "DeltaFast" => oarray::FitnessFunction::DeltaFast,
-        "Cidev" => oarray::FitnessFunction::Cidev,
-        "Comb" => oarray::FitnessFunction::Comb(get_arg!(matches, "fitness-exp", u32)),
-        "SheerLuck" => oarray::FitnessFunction::SheerLuck,
-        "Walsh" => oarray::FitnessFunction::Walsh(get_arg!(matches, "fitness-exp", u32)),
-        "WalshFast" => oarray::FitnessFunction::WalshFaster(get_arg!(matches, "fitness-exp", u32)),
-        _ => panic!("Invalid function name"),
-    };
+    let f = oarray::fitness_by_name(
+        matches.value_of("fitness").unwrap(),
+        get_arg!(matches, "fitness-exp", u32),
+    )
+    .expect("Invalid function name");
     let ngrande = get_arg!(matches, "N", usize);
     let params = run::RunParameters {
         ngrande,
@@ -146,8 +199,20 @@ fn main() {
             .unwrap_or((ngrande as f64).log2().round() as usize),
         mutation_prob: get_arg!(matches, "mutation-prob", f64),
         fitness_f: f,
-        breed_factor: get_arg!(matches, "breed-factor", f64),
-        survival_factor: get_arg!(matches, "survival-factor", f64),
+        functions: get_arg!(matches, "functions", FunctionSet),
+        balanced: get_arg!(matches, "balanced", bool),
+        notation: get_arg!(matches, "notation", Notation),
+        simplify: get_arg!(matches, "simplify", bool),
+        cache_size: get_arg!(matches, "cache-size", usize),
+        epoch: ConfigurableEpoch::from_names(
+            matches.value_of("selection").unwrap(),
+            matches.value_of("replacement").unwrap(),
+            get_arg!(matches, "tournament-size", usize),
+            get_arg!(matches, "breed-factor", f64),
+            get_arg!(matches, "survival-factor", f64),
+            get_arg!(matches, "offspring", usize),
+        )
+        .expect("Invalid epoch configuration"),
         silent: get_arg!(matches, "silent", bool),
     };
     let runs = get_arg!(matches, "runs", usize);
@@ -179,6 +244,12 @@ fn main() {
         "Looking for OA[N: {}, k: {}, s: 2, t: {}]",
         params.ngrande, params.k, params.t
     );
+    if let Some(violation) = oarray::bounds::check_parameters(params.ngrande, params.k, params.t) {
+        warn!(
+            "OA[N: {}, k: {}, s: 2, t: {}] cannot exist: {}",
+            params.ngrande, params.k, params.t, violation
+        );
+    }
     debug!("{:#?}", params);
 
     let show_progress = threads == 1 && !params.silent;
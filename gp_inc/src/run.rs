@@ -4,14 +4,11 @@ use evco::gp::*;
 use rand::OsRng;
 use std::f64;
 
-//mod epoch;
-use epoch::TournamentEpoch;
-//use spiril::epoch::DefaultEpoch;
-
-use gpoarray::IncGPOArray;
-use oarray::{FitnessFunction, OArray};
+use gpoarray::{GpParameters, IncGPOArray};
+use ga_common::ConfigurableEpoch;
+use oarray::{FitnessCache, FitnessFunction, OArray};
 use pbr::ProgressBar;
-//use treeformula::TreeFormula;
+use gp_common::{FunctionSet, Notation};
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct RunParameters {
@@ -23,43 +20,50 @@ pub(crate) struct RunParameters {
     pub max_depth: usize,
     pub epochs: usize,
     pub fitness_f: FitnessFunction,
+    pub functions: FunctionSet,
+    /// colonne bilanciate per costruzione
+    pub balanced: bool,
+    pub notation: Notation,
+    /// semplifica le formule trovate prima di stamparle
+    pub simplify: bool,
     pub silent: bool,
-    pub breed_factor: f64,
-    pub survival_factor: f64
+    pub epoch: ConfigurableEpoch,
+    pub cache_size: usize,
 }
 
 pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
     let ngrande = p.ngrande;
     assert!(ngrande % (2usize.pow(p.t)) == 0, "2^t non divide N");
+    if p.balanced {
+        assert!(ngrande.is_power_of_two() && ngrande >= 4, "N deve essere una potenza di 2 almeno 4");
+    }
     let mut partial = OArray::generate_partial(ngrande, p.t, p.fitness_f);
     let mut k_current = p.t as usize;
-    let epoch = TournamentEpoch::new();
-    //let epoch = DefaultEpoch::new(p.breed_factor, p.survival_factor);
+    let epoch = p.epoch;
     let crossover = Crossover::hard_prune(p.max_depth);
     //let crossover = Crossover::one_point_leaf_biased(leaf_bias);
     let mutation = Mutation::uniform_prune(p.max_depth);
     //let mutation= Mutation::uniform();
     let rng = OsRng::new().unwrap();
+    let params = GpParameters {
+        max_depth: p.max_depth,
+        crossover,
+        mutation,
+        mutation_prob: p.mutation_prob,
+        functions: p.functions,
+        balanced: p.balanced,
+    };
     let mut formulas = Vec::new();
     while k_current < p.k {
         let num_epochs = p.epochs * (k_current + 1 - p.t as usize);
         let best;
         let mut cnt = 0;
+        //le fitness dipendono dalle colonne già fissate: una cache per colonna
+        let cache = FitnessCache::new(p.cache_size);
         {
             let units: Vec<IncGPOArray<_>> = (0..p.pop_size)
                 .map(|_| {
-                    IncGPOArray::new_rand(
-                        &partial,
-                        p.ngrande,
-                        p.k,
-                        p.t,
-                        p.max_depth,
-                        rng.clone(),
-                        crossover,
-                        mutation,
-                        p.mutation_prob,
-                        p.fitness_f,
-                    )
+                    IncGPOArray::new_rand(&partial, p.k, &params, rng.clone(), &cache)
                 }).collect();
 
             let mut pbar = ProgressBar::new(num_epochs as u64);
@@ -87,7 +91,8 @@ pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
                 .iter()
                 .max_by(|&a, &b| a.fitness().partial_cmp(&b.fitness()).unwrap())
                 .unwrap();
-            formulas.push(best1.tree.clone());
+            debug!("Fitness cache, column {}: {}", k_current, cache);
+            formulas.push(best1.formula());
             best = best1.to_oarray();
         }
         if -best.fitness() < f64::EPSILON {
@@ -99,8 +104,11 @@ pub(crate) fn run(p: &RunParameters, show_progress: bool) -> (bool, bool) {
     }
     if -partial.fitness() < f64::EPSILON && partial.k == p.k {
         debug!("{}", partial);
+        let n = (ngrande as f64).log2().ceil() as usize;
         for (i, f) in formulas.iter().enumerate() {
-            debug!("Formula {}: {}", i + 1, f);
+            let f = if p.simplify { f.simplify(n) } else { f.clone() };
+            debug!("Formula {}: {}", i + 1, f.print(p.notation, n));
+            debug!("ANF {}: {}", i + 1, f.anf(n));
         }
         if p.silent {
             println!("{:?}", partial);
@@ -1,45 +1,96 @@
+extern crate clap;
 extern crate oarray;
 
 //use simplelog::*;
-use std::env;
+use clap::{App, Arg};
+use oarray::{open_oarrays, OArray, WalshClimber};
+use std::io;
 
-mod hill_climb;
-use oarray::OArray;
-use std::io::{self};
-
-fn main() {
-    let t = env::args()
-        .nth(1)
-        .expect("Fornire parametro t come argomento")
+fn main() -> io::Result<()> {
+    let matches = App::new("hill_local")
+        .about("Improves each OA in the input by hill climbing on its Walsh spectrum")
+        .arg(
+            Arg::with_name("t")
+                .help("the strength of the OA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("files")
+                .help("OA files, with arrays separated by blank lines or ---. Use `-` for standard input")
+                .multiple(true)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::with_name("max-swaps")
+                .long("max-swaps")
+                .help("Maximum number of swaps per improvement set")
+                .default_value("2"),
+        )
+        .get_matches();
+    let t: u32 = matches
+        .value_of("t")
+        .unwrap()
         .parse()
-        .unwrap();
-    'outer: loop {
-        let mut buffer = String::new();
-        while let Ok(num) = io::stdin().read_line(&mut buffer) {
-            if num == 1 {
-                break;
+        .expect("Invalid value for t");
+    let max_swaps: usize = matches
+        .value_of("max-swaps")
+        .unwrap()
+        .parse()
+        .expect("Invalid value for max-swaps");
+    let mut index = 0;
+    for f in matches.values_of_os("files").unwrap() {
+        for oa in open_oarrays(f)? {
+            match oa {
+                Ok(oa) => climb(oa, t, max_swaps, index),
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                    eprintln!("{}: {}", f.to_string_lossy(), e);
+                    continue;
+                }
+                Err(e) => return Err(e),
             }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn climb(mut oa: OArray, t: u32, max_swaps: usize, index: usize) {
+    oa.fitness_f = oarray::FitnessFunction::WalshFaster(2);
+    oa.target_t = t;
+    let old_fit = oa.fitness();
+    let mut climber = match WalshClimber::new(&oa) {
+        Some(c) => c,
+        None => {
+            println!("{}: nisba (k troppo grande)", index);
+            return;
+        }
+    };
+    let mut count = 0;
+    loop {
+        if climber.walsh_fitness() > -1.0 {
+            let out = climber.to_oarray(t, oa.fitness_f);
+            println!("{}:", index);
+            println!("{}", oa);
+            println!("{}", out);
+            println!("{}, {}, {}", old_fit, out.fitness(), count);
+            break;
         }
-        let mut oa = OArray::from(buffer.trim());
-        oa.fitness_f = oarray::FitnessFunction::WalshFaster(2);
-        oa.target_t = t;
-        let old_fit = oa.fitness();
-        let old_oa = oa.clone();
-        let mut count = 0;
-        loop {
-            let out = hill_climb::hill_climb(oa);
-            if out.0.fitness() > -1.0{
-                println!("{}", old_oa);
-                println!("{}", out.0);
-                println!("{}, {}, {}", old_fit, out.0.fitness(), count);
-                break 'outer;
+        match climber.find_improvement(max_swaps) {
+            Some(set) => {
+                println!(
+                    "{}: {} scambi: cidev {:+}, Walsh(2) {:+}",
+                    index,
+                    set.swaps.len(),
+                    set.cidev_delta,
+                    set.fitness_delta
+                );
+                climber.apply(&set);
             }
-            if !out.1 {
-                println!("nisba ({})",count);
+            None => {
+                println!("{}: nisba ({})", index, count);
                 break;
             }
-            oa = out.0;
-            count += 1;
         }
+        count += 1;
     }
 }
@@ -0,0 +1,164 @@
+//! Forma normale algebrica (ANF) delle funzioni booleane, calcolata
+//! con la trasformata di Möbius veloce.
+//! Il monomio di indice `u` è il prodotto delle variabili x_i per cui
+//! il bit i di `u` vale 1.
+
+use binary_strings::usize_hamming_weight;
+use oarray::OArray;
+use std::cmp::Reverse;
+use std::fmt::{Display, Error, Formatter};
+use wtform::TruthTable;
+
+pub struct Anf {
+    pub coeffs: Vec<bool>,
+    pub log2len: usize,
+}
+
+impl TruthTable {
+    pub fn anf(&self) -> Anf {
+        let mut coeffs = self.table.clone();
+        moebius_tform(&mut coeffs);
+        Anf {
+            coeffs,
+            log2len: self.log2len,
+        }
+    }
+    pub fn algebraic_degree(&self) -> usize {
+        self.anf().degree()
+    }
+}
+
+impl OArray {
+    /// ANF della funzione caratteristica dell'array, con la colonna j come
+    /// variabile x_j: `truth_table` mette invece la colonna 0 nel bit più alto
+    pub fn anf(&self) -> Anf {
+        let anf = self.truth_table().anf();
+        let mut coeffs = vec![false; anf.coeffs.len()];
+        for (u, &c) in anf.coeffs.iter().enumerate() {
+            let mut reversed = 0;
+            for i in (0..self.k).filter(|i| u & (1 << i) != 0) {
+                reversed |= 1 << (self.k - 1 - i);
+            }
+            coeffs[reversed] = c;
+        }
+        Anf {
+            coeffs,
+            log2len: self.k,
+        }
+    }
+}
+
+impl Anf {
+    /// Grado algebrico: peso massimo di un monomio con coefficiente 1.
+    /// Per convenzione la funzione nulla ha grado 0.
+    pub fn degree(&self) -> usize {
+        self.coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c)
+            .map(|(u, _)| usize_hamming_weight(u))
+            .max()
+            .unwrap_or(0)
+    }
+    /// Ricostruisce la tabella di verità (la trasformata di Möbius è un'involuzione)
+    pub fn truth_table(&self) -> TruthTable {
+        let mut table = self.coeffs.clone();
+        moebius_tform(&mut table);
+        TruthTable {
+            table,
+            log2len: self.log2len,
+        }
+    }
+}
+
+impl Display for Anf {
+    /// Stampa il polinomio, es. `x0*x2 + x1 + 1`
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let mut monomials: Vec<usize> = self
+            .coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c)
+            .map(|(u, _)| u)
+            .collect();
+        if monomials.is_empty() {
+            return write!(f, "0");
+        }
+        //monomi di grado più alto per primi, poi in ordine lessicografico
+        monomials.sort_by_key(|&u| (Reverse(usize_hamming_weight(u)), u));
+        for (n, u) in monomials.iter().enumerate() {
+            if n > 0 {
+                write!(f, " + ")?;
+            }
+            if *u == 0 {
+                write!(f, "1")?;
+                continue;
+            }
+            let vars: Vec<String> = (0..self.log2len)
+                .filter(|i| u & (1 << i) != 0)
+                .map(|i| format!("x{}", i))
+                .collect();
+            write!(f, "{}", vars.join("*"))?;
+        }
+        Ok(())
+    }
+}
+
+fn moebius_tform(v: &mut [bool]) {
+    let mut step = 1;
+    while step < v.len() {
+        for j in 0..v.len() {
+            if j & step != 0 {
+                v[j] ^= v[j ^ step];
+            }
+        }
+        step <<= 1;
+    }
+}
+
+#[test]
+fn test_anf() {
+    // f = x0 + x1*x2
+    let table = (0..8usize)
+        .map(|x| (x & 1 != 0) ^ (x & 2 != 0 && x & 4 != 0))
+        .collect();
+    let tt = TruthTable::new(table);
+    let anf = tt.anf();
+    let mut expected = vec![false; 8];
+    expected[1] = true;
+    expected[6] = true;
+    assert!(anf.coeffs == expected);
+    assert!(anf.degree() == 2);
+    assert!(format!("{}", anf) == "x1*x2 + x0");
+    assert!(anf.truth_table().table == tt.table);
+}
+
+#[test]
+fn test_anf_roundtrip() {
+    use fitness::FitnessFunction;
+    use oarray::OArray;
+    use rand::thread_rng;
+    let mut rng = thread_rng();
+    for _ in 0..100 {
+        let oa = OArray::new_random_balanced(16, 6, 2, &mut rng, FitnessFunction::Delta);
+        let tt = oa.truth_table();
+        let anf = tt.anf();
+        assert!(anf.truth_table().table == tt.table);
+        assert!(anf.degree() <= oa.k);
+    }
+    let constant = TruthTable::new(vec![true; 4]);
+    assert!(constant.algebraic_degree() == 0);
+    assert!(format!("{}", constant.anf()) == "1");
+    assert!(format!("{}", TruthTable::new(vec![false; 4]).anf()) == "0");
+}
+
+#[test]
+fn test_oarray_anf() {
+    use fitness::FitnessFunction;
+    let single = OArray::new(2, 1, 1, vec![true, true], FitnessFunction::Delta);
+    assert!(single.anf().to_string() == "x0");
+    //righe (1,0) e (1,1): la funzione vale 1 se e solo se la colonna 0 vale 1
+    let oa = OArray::new(2, 2, 1, vec![true, true, false, true], FitnessFunction::Delta);
+    assert!(oa.anf().to_string() == "x0");
+    assert!(oa.anf().degree() == oa.truth_table().algebraic_degree());
+}
@@ -0,0 +1,231 @@
+//! Limiti inferiori sul numero di righe N di un OA(N, k, 2, t):
+//! il limite di Rao e il limite di programmazione lineare di Delsarte.
+
+use features::{binomial, krawtchouk};
+use std::fmt::{Display, Error, Formatter};
+
+/// Tolleranza numerica usata dal simplesso
+const LP_EPSILON: f64 = 1e-9;
+
+/// Limite di Rao per un OA binario di forza `t` con `k` colonne.
+pub fn rao_bound(k: usize, t: u32) -> usize {
+    let t = t as usize;
+    let u = t / 2;
+    let mut out: i64 = (0..=u).map(|i| binomial(k, i)).sum();
+    if t % 2 == 1 && k > 0 {
+        out += binomial(k - 1, u);
+    }
+    out as usize
+}
+
+/// Limite di programmazione lineare di Delsarte per un OA binario di forza `t`
+/// con `k` colonne: minimo di sum_i A_i sulle distribuzioni delle distanze con
+/// A_0 = 1, A_i >= 0, trasformata di MacWilliams nulla per 1 <= j <= t e
+/// non negativa per j > t.
+pub fn delsarte_bound(k: usize, t: u32) -> f64 {
+    let t = (t as usize).min(k);
+    // variabili: A_1..A_k, poi una variabile di slack per ogni j in t+1..k
+    let n_vars = k + (k - t);
+    let mut a = Vec::with_capacity(k);
+    let mut b = Vec::with_capacity(k);
+    for j in 1..=k {
+        let mut row = vec![0.0; n_vars];
+        for i in 1..=k {
+            row[i - 1] = -krawtchouk(k, j, i) as f64;
+        }
+        if j > t {
+            row[k + j - t - 1] = 1.0;
+        }
+        a.push(row);
+        b.push(binomial(k, j) as f64);
+    }
+    let mut c = vec![1.0; k];
+    c.resize(n_vars, 0.0);
+    1.0 + simplex(&c, &a, &b).expect("Il problema di Delsarte è sempre ammissibile")
+}
+
+/// Limite violato da una terna di parametri (N, k, t)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    Rao(usize),
+    Delsarte(f64),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Violation::Rao(b) => write!(f, "Rao bound requires N >= {}", b),
+            Violation::Delsarte(b) => write!(f, "Delsarte LP bound requires N >= {:.4}", b),
+        }
+    }
+}
+
+/// Controlla se un OA(`ngrande`, `k`, 2, `t`) può esistere.
+/// Restituisce il primo limite violato, se presente.
+pub fn check_parameters(ngrande: usize, k: usize, t: u32) -> Option<Violation> {
+    let rao = rao_bound(k, t);
+    if ngrande < rao {
+        return Some(Violation::Rao(rao));
+    }
+    let lp = delsarte_bound(k, t);
+    if (ngrande as f64) < lp - 1e-6 {
+        return Some(Violation::Delsarte(lp));
+    }
+    None
+}
+
+/// Risolve min c^T x con A x = b, x >= 0, b >= 0, usando il simplesso a due fasi
+/// con la regola di Bland. Restituisce il valore ottimo, oppure `None` se il
+/// problema non è ammissibile o è illimitato.
+fn simplex(c: &[f64], a: &[Vec<f64>], b: &[f64]) -> Option<f64> {
+    let m = a.len();
+    let n = c.len();
+    //tableau con una variabile artificiale per riga, ultima colonna = termine noto
+    let mut tab: Vec<Vec<f64>> = a
+        .iter()
+        .zip(b.iter())
+        .enumerate()
+        .map(|(i, (row, &b_i))| {
+            debug_assert!(b_i >= 0.0);
+            let mut r = row.clone();
+            r.resize(n + m, 0.0);
+            r[n + i] = 1.0;
+            r.push(b_i);
+            r
+        })
+        .collect();
+    let mut basis: Vec<usize> = (n..n + m).collect();
+
+    //fase 1: minimizza la somma delle variabili artificiali
+    let mut cost = vec![0.0; n + m];
+    for x in cost.iter_mut().skip(n) {
+        *x = 1.0;
+    }
+    if !pivot_loop(&mut tab, &mut basis, &cost, n + m) {
+        return None;
+    }
+    if objective(&tab, &basis, &cost) > LP_EPSILON {
+        return None;
+    }
+    //porta fuori base le variabili artificiali rimaste (a valore 0)
+    for r in 0..m {
+        if basis[r] >= n {
+            if let Some(col) = (0..n).find(|&j| tab[r][j].abs() > LP_EPSILON) {
+                pivot(&mut tab, &mut basis, r, col);
+            }
+        }
+    }
+
+    //fase 2: le variabili artificiali non possono più entrare in base
+    let mut cost = c.to_vec();
+    cost.resize(n + m, 0.0);
+    if !pivot_loop(&mut tab, &mut basis, &cost, n) {
+        return None;
+    }
+    Some(objective(&tab, &basis, &cost))
+}
+
+fn objective(tab: &[Vec<f64>], basis: &[usize], cost: &[f64]) -> f64 {
+    basis
+        .iter()
+        .zip(tab.iter())
+        .map(|(&var, row)| cost[var] * row[row.len() - 1])
+        .sum()
+}
+
+/// Itera i pivot finché nessuna delle prime `allowed` colonne ha costo ridotto
+/// negativo. Restituisce `false` se il problema è illimitato.
+fn pivot_loop(tab: &mut [Vec<f64>], basis: &mut [usize], cost: &[f64], allowed: usize) -> bool {
+    loop {
+        let reduced = |j: usize| {
+            cost[j]
+                - basis
+                    .iter()
+                    .zip(tab.iter())
+                    .map(|(&var, row)| cost[var] * row[j])
+                    .sum::<f64>()
+        };
+        let entering = (0..allowed).find(|&j| !basis.contains(&j) && reduced(j) < -LP_EPSILON);
+        let col = match entering {
+            Some(col) => col,
+            None => return true,
+        };
+        let last = tab[0].len() - 1;
+        let mut leaving: Option<(usize, f64)> = None;
+        for (r, row) in tab.iter().enumerate() {
+            if row[col] > LP_EPSILON {
+                let ratio = row[last] / row[col];
+                leaving = match leaving {
+                    Some((best, best_ratio))
+                        if best_ratio < ratio - LP_EPSILON
+                            || (ratio - best_ratio).abs() <= LP_EPSILON
+                                && basis[best] < basis[r] =>
+                    {
+                        Some((best, best_ratio))
+                    }
+                    _ => Some((r, ratio)),
+                };
+            }
+        }
+        match leaving {
+            Some((row, _)) => pivot(tab, basis, row, col),
+            None => return false,
+        }
+    }
+}
+
+fn pivot(tab: &mut [Vec<f64>], basis: &mut [usize], row: usize, col: usize) {
+    let p = tab[row][col];
+    for x in tab[row].iter_mut() {
+        *x /= p;
+    }
+    let pivot_row = tab[row].clone();
+    for (r, other) in tab.iter_mut().enumerate() {
+        if r != row {
+            let factor = other[col];
+            if factor != 0.0 {
+                for (x, y) in other.iter_mut().zip(pivot_row.iter()) {
+                    *x -= factor * y;
+                }
+            }
+        }
+    }
+    basis[row] = col;
+}
+
+#[test]
+fn test_simplex() {
+    // min x + y con x + 2y = 4, x - y + s = 1
+    let c = [1.0, 1.0, 0.0];
+    let a = vec![vec![1.0, 2.0, 0.0], vec![1.0, -1.0, 1.0]];
+    let b = [4.0, 1.0];
+    let opt = simplex(&c, &a, &b).unwrap();
+    assert!((opt - 2.0).abs() < 1e-9);
+    // x = 1, x = 2: non ammissibile
+    let c = [1.0];
+    let a = vec![vec![1.0], vec![1.0]];
+    assert!(simplex(&c, &a, &[1.0, 2.0]).is_none());
+}
+
+#[test]
+fn test_rao() {
+    assert!(rao_bound(7, 2) == 8);
+    assert!(rao_bound(8, 3) == 16);
+    assert!(rao_bound(9, 3) == 18);
+    assert!(rao_bound(15, 4) == 121);
+}
+
+#[test]
+fn test_delsarte() {
+    assert!((delsarte_bound(7, 2) - 8.0).abs() < 1e-6);
+    assert!((delsarte_bound(8, 3) - 16.0).abs() < 1e-6);
+    for k in 2..12 {
+        for t in 1..=k as u32 {
+            assert!(delsarte_bound(k, t) >= rao_bound(k, t) as f64 - 1e-6);
+        }
+    }
+    assert!(check_parameters(8, 7, 2).is_none());
+    assert!(check_parameters(16, 8, 3).is_none());
+    assert!(check_parameters(16, 9, 3).is_some());
+    assert!(check_parameters(4, 4, 2).is_some());
+}
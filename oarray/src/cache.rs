@@ -0,0 +1,160 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Valore con cui si inizializza il secondo hash, per renderlo
+/// indipendente dal primo
+const CHECK_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Due hash indipendenti della chiave: il primo indicizza la cache, il
+/// secondo riconosce le collisioni del primo senza conservare la chiave
+fn hash_key<K: Hash + ?Sized>(key: &K) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let mut check = DefaultHasher::new();
+    check.write_u64(CHECK_SALT);
+    key.hash(&mut check);
+    (hasher.finish(), check.finish())
+}
+
+struct CacheInner {
+    /// il secondo hash della chiave, per riconoscere le collisioni del primo
+    map: HashMap<u64, (u64, f64)>,
+    /// ordine di inserimento, per eliminare le voci più vecchie
+    order: VecDeque<u64>,
+}
+
+/// Cache delle fitness già calcolate, condivisa tra le unità di una run.
+/// Contiene al più `capacity` valori; quando è piena elimina il più vecchio.
+pub struct FitnessCache {
+    inner: Mutex<CacheInner>,
+    capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl FitnessCache {
+    /// Con `capacity == 0` la cache è disattivata
+    pub fn new(capacity: usize) -> Self {
+        FitnessCache {
+            inner: Mutex::new(CacheInner {
+                map: HashMap::with_capacity(capacity),
+                order: VecDeque::with_capacity(capacity),
+            }),
+            capacity,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+    /// Restituisce la fitness associata a `key`, calcolandola con `f`
+    /// se non è presente. Il calcolo avviene senza tenere il lock.
+    /// La chiave può essere il genotipo o una sua forma canonica, ad esempio
+    /// le formule di un individuo GP, così un successo evita di valutarle.
+    pub fn get_or_insert_with<K: Hash + ?Sized, F: FnOnce() -> f64>(&self, key: &K, f: F) -> f64 {
+        if self.capacity == 0 {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return f();
+        }
+        let (key, check) = hash_key(key);
+        self.get_or_insert_hashed(key, check, f)
+    }
+    /// Come `get_or_insert_with`, con gli hash già calcolati: se la voce
+    /// trovata ha un secondo hash diverso la fitness viene ricalcolata e la sostituisce
+    fn get_or_insert_hashed<F: FnOnce() -> f64>(&self, key: u64, check: u64, f: F) -> f64 {
+        if let Some(&(cached, fit)) = self.inner.lock().unwrap().map.get(&key) {
+            if cached == check {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return fit;
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let fit = f();
+        let mut inner = self.inner.lock().unwrap();
+        if inner.map.insert(key, (check, fit)).is_none() {
+            inner.order.push_back(key);
+            if inner.order.len() > self.capacity {
+                let old = inner.order.pop_front().unwrap();
+                inner.map.remove(&old);
+            }
+        }
+        fit
+    }
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().map.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+    /// Frazione delle richieste servite dalla cache
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits();
+        let tot = hits + self.misses();
+        if tot == 0 {
+            0.0
+        } else {
+            hits as f64 / tot as f64
+        }
+    }
+}
+
+impl Display for FitnessCache {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(
+            f,
+            "{} hits, {} misses, hit rate {:.2}%, {} entries",
+            self.hits(),
+            self.misses(),
+            100.0 * self.hit_rate(),
+            self.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let cache = FitnessCache::new(2);
+        let mut evals = 0;
+        for &key in &[1, 2, 1, 3, 1, 2] {
+            let genotype: Vec<bool> = (0..4).map(|i| key >> i & 1 == 1).collect();
+            cache.get_or_insert_with(&genotype, || {
+                evals += 1;
+                -(key as f64)
+            });
+        }
+        //1 e 2 calcolati, 1 trovato, 3 inserito eliminando 1, 1 e 2 ricalcolati
+        assert!(evals == 5);
+        assert!(cache.hits() == 1 && cache.misses() == 5);
+        assert!(cache.len() == 2);
+        assert!(cache.get_or_insert_with(&[false, true, false, false], || 0.0) == -2.0);
+
+        let disabled = FitnessCache::new(0);
+        disabled.get_or_insert_with(&[true], || 0.0);
+        assert!(disabled.get_or_insert_with(&[true], || -1.0) == -1.0);
+        assert!(disabled.hits() == 0 && disabled.is_empty());
+    }
+    #[test]
+    fn test_collision() {
+        //due chiavi diverse con lo stesso primo hash non condividono la fitness
+        let cache = FitnessCache::new(4);
+        assert!(cache.get_or_insert_hashed(7, 1, || -1.0) == -1.0);
+        assert!(cache.get_or_insert_hashed(7, 2, || -2.0) == -2.0);
+        assert!(cache.get_or_insert_hashed(7, 2, || 0.0) == -2.0);
+        assert!(cache.hits() == 1 && cache.misses() == 2);
+        assert!(cache.len() == 1);
+        let (a, b) = hash_key(&[true, false][..]);
+        assert!(a != b && hash_key(&vec![true, false]) == (a, b));
+    }
+}
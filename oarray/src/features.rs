@@ -32,6 +32,75 @@ impl OArray {
         }
         tmp.iter().map(|&i| i as f64 /self.ngrande as f64).collect()
     }
+    /// Distribuzione duale, ottenuta applicando la trasformata di MacWilliams
+    /// alla distribuzione delle distanze: B_j = 1/N * sum_i A_i K_j(i).
+    pub fn dual_weight_d(&self) -> Vec<f64> {
+        let a = self.proper_weight_d();
+        (0..=self.k)
+            .map(|j| {
+                let tot: f64 = a
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &a_i)| a_i * krawtchouk(self.k, j, i) as f64)
+                    .sum();
+                tot / self.ngrande as f64
+            })
+            .collect()
+    }
+    /// Distanza duale: il minimo j > 0 tale che B_j != 0.
+    /// `None` se l'array contiene tutte le 2^k righe (B_j = 0 per ogni j > 0).
+    pub fn dual_distance(&self) -> Option<usize> {
+        self.dual_weight_d()
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(_, &b)| b.abs() > DUAL_EPSILON)
+            .map(|(j, _)| j)
+    }
+    /// Forza effettiva dell'array: distanza duale - 1.
+    pub fn strength(&self) -> usize {
+        match self.dual_distance() {
+            Some(d) => d - 1,
+            None => self.k,
+        }
+    }
+    /// Controllo di coerenza sulla distribuzione duale: deve essere non
+    /// negativa e sommare a A_0 * 2^k / N. Vale per ogni array, quindi serve
+    /// solo a scoprire errori nel calcolo; il limite di programmazione lineare
+    /// di Delsarte è `bounds::delsarte_bound`.
+    pub fn check_dual_distribution(&self) -> bool {
+        let a_0 = self.proper_weight_d()[0];
+        let b = self.dual_weight_d();
+        let expected = a_0 * 2f64.powi(self.k as i32) / self.ngrande as f64;
+        let tot: f64 = b.iter().sum();
+        b.iter().all(|&b_j| b_j > -DUAL_EPSILON) && (tot - expected).abs() < DUAL_EPSILON * expected
+    }
+}
+
+/// Tolleranza usata per confrontare con zero i coefficienti della distribuzione duale
+const DUAL_EPSILON: f64 = 1e-9;
+
+/// Coefficiente binomiale C(n, k), 0 se k > n.
+pub fn binomial(n: usize, k: usize) -> i64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    let mut out = 1i128;
+    for i in 0..k {
+        out = out * (n - i) as i128 / (i + 1) as i128;
+    }
+    out as i64
+}
+
+/// Polinomio di Krawtchouk binario K_j(i) di grado j, per parole di lunghezza n.
+pub fn krawtchouk(n: usize, j: usize, i: usize) -> i64 {
+    (0..=j)
+        .map(|s| {
+            let sign = if s % 2 == 0 { 1 } else { -1 };
+            sign * binomial(i, s) * binomial(n - i, j - s)
+        })
+        .sum()
 }
 
 fn hamming_dist(a: &[bool], b: &[bool]) -> usize {
@@ -66,3 +135,39 @@ fn test_zero_w() {
     println!("{}", oa);
     assert!(oa.zero_weight_d() == result);
 }
+
+#[test]
+fn test_krawtchouk() {
+    assert!(binomial(5, 2) == 10);
+    assert!(binomial(2, 3) == 0);
+    assert!(krawtchouk(3, 1, 2) == -1);
+    assert!(krawtchouk(3, 2, 2) == -1);
+    assert!(krawtchouk(3, 3, 2) == 1);
+    for i in 0..=4 {
+        let tot: i64 = (0..=4).map(|j| krawtchouk(4, j, i)).sum();
+        assert!(tot == if i == 0 { 16 } else { 0 });
+    }
+}
+
+#[test]
+fn test_dual_w() {
+    use super::FitnessFunction;
+    let d = vec![
+        false, false, true, true, false, true, false, true, false, true, true, false,
+    ];
+    let oa = OArray::new(4, 3, 2, d, FitnessFunction::Delta);
+    let dual = oa.dual_weight_d();
+    let expected = [1.0, 0.0, 0.0, 1.0];
+    assert!(dual.iter().zip(expected.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+    assert!(oa.dual_distance() == Some(3));
+    assert!(oa.strength() == 2);
+    assert!(oa.check_dual_distribution());
+
+    let d = vec![
+        false, true, false, true, false, true, false, true, false, false, true, true, false, false,
+        true, true, false, false, false, false, true, true, true, true,
+    ];
+    let full = OArray::new(8, 3, 2, d, FitnessFunction::Delta);
+    assert!(full.dual_distance().is_none());
+    assert!(full.strength() == 3);
+}
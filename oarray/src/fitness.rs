@@ -1,27 +1,209 @@
+use features::binomial;
 use oarray::OArray;
+use std::fmt::{Debug, Error, Formatter};
+use std::sync::Mutex;
 use streaming_iterator::StreamingIterator;
 use t_combinations::{combinations_descent, Combinations};
-use wtform::PolarTruthTable;
+
+/// Funzione di fitness: valori <= 0, dove 0 indica un OA di forza `target_t`.
+/// Si può implementare fuori da questo crate e registrare per nome con
+/// `register_fitness`, in modo che i programmi la possano selezionare.
+pub trait Fitness: Send + Sync {
+    /// Fitness dell'array completo
+    fn fitness(&self, oa: &OArray) -> f64;
+    /// Fitness dell'array ottenuto aggiungendo `last_col` a `partial`,
+    /// sapendo che `partial` è già un OA di forza `target_t`.
+    /// Di default costruisce l'array completo.
+    fn incremental_fitness(&self, partial: &OArray, last_col: &[bool]) -> f64 {
+        let mut oa = partial.clone();
+        oa.d.extend_from_slice(last_col);
+        oa.k += 1;
+        self.fitness(&oa)
+    }
+}
+
+/// Costruttore di una fitness registrata, a partire dall'esponente
+pub type FitnessConstructor = fn(u32) -> Box<dyn Fitness>;
+
+/// Fitness registrata, con le istanze già costruite (una per esponente)
+struct Registered {
+    name: &'static str,
+    constructor: FitnessConstructor,
+    instances: Vec<(u32, &'static dyn Fitness)>,
+}
+
+static REGISTRY: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+/// Registra una fitness definita dall'utente con il nome `name`,
+/// che diventa selezionabile con `fitness_by_name`
+pub fn register_fitness(name: &'static str, constructor: FitnessConstructor) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|r| r.name != name);
+    registry.push(Registered {
+        name,
+        constructor,
+        instances: Vec::new(),
+    });
+}
+
+/// Fitness predefinita: nome usato sulla linea di comando e costruttore a
+/// partire dall'esponente, ignorato da quelle non parametriche
+type Builtin = (&'static str, fn(u32) -> FitnessFunction);
+
+const BUILTIN: &[Builtin] = &[
+    ("Delta", |_| Delta),
+    ("DeltaFast", |_| DeltaFast),
+    ("DeltaP", DeltaP),
+    ("DeltaNorm", DeltaNormalized),
+    ("Cidev", |_| Cidev),
+    ("SheerLuck", |_| SheerLuck),
+    ("Comb", Comb),
+    ("Walsh", Walsh),
+    ("WalshFast", WalshFaster),
+    ("WalshRec", WalshRec),
+    ("WalshNorm", WalshNormalized),
+    ("WalshRestrictedMax", |_| WalshRestricted(WalshAggregation::Max)),
+    ("WalshRestrictedSum", |e| WalshRestricted(WalshAggregation::Sum(e))),
+    ("WalshRestrictedNonZero", |_| WalshRestricted(WalshAggregation::NonZero)),
+];
+
+/// Nomi accettati da `fitness_by_name`: le fitness predefinite, poi quelle registrate
+pub fn fitness_names() -> Vec<&'static str> {
+    let registry = REGISTRY.lock().unwrap();
+    BUILTIN
+        .iter()
+        .map(|&(name, _)| name)
+        .chain(registry.iter().map(|r| r.name))
+        .collect()
+}
+
+/// Cerca una fitness per nome tra quelle predefinite e quelle registrate.
+/// `exponent` viene usato dalle fitness parametriche.
+pub fn fitness_by_name(name: &str, exponent: u32) -> Option<FitnessFunction> {
+    if let Some(&(_, builtin)) = BUILTIN.iter().find(|&&(n, _)| n == name) {
+        return Some(builtin(exponent));
+    }
+    let mut registry = REGISTRY.lock().unwrap();
+    let entry = registry.iter_mut().find(|r| r.name == name)?;
+    let f = match entry.instances.iter().find(|&&(e, _)| e == exponent) {
+        Some(&(_, f)) => f,
+        None => {
+            //le fitness vivono per tutta la durata del programma:
+            //se ne costruisce una sola per esponente
+            let f: &'static dyn Fitness = Box::leak((entry.constructor)(exponent));
+            entry.instances.push((exponent, f));
+            f
+        }
+    };
+    Some(Custom(CustomFitness {
+        name: entry.name,
+        f,
+    }))
+}
+
+/// Riferimento a una fitness registrata
+#[derive(Clone, Copy)]
+pub struct CustomFitness {
+    pub name: &'static str,
+    f: &'static dyn Fitness,
+}
+impl PartialEq for CustomFitness {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+impl Eq for CustomFitness {}
+impl Debug for CustomFitness {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{}", self.name)
+    }
+}
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum FitnessFunction {
     Delta,
     DeltaFast,
+    DeltaP(u32),          //p della norma
+    DeltaNormalized(u32), //p della norma
     Walsh(u32),       //exponent
     WalshFaster(u32), //exponent
     WalshRec(u32),
     Cidev,
     SheerLuck,
     Comb(u32),
+    WalshRestricted(WalshAggregation),
+    WalshNormalized(u32), //exponent
+    Custom(CustomFitness),
 }
 pub use self::FitnessFunction::*;
 
+impl Fitness for FitnessFunction {
+    fn fitness(&self, oa: &OArray) -> f64 {
+        match *self {
+            Delta => oa.delta_fitness(),
+            DeltaFast => oa.delta_fitness_fast(),
+            DeltaP(p) => oa.delta_fitness_p(p),
+            DeltaNormalized(p) => oa.delta_fitness_p(p) / oa.delta_normalization(p),
+            Walsh(exponent) => oa.walsh_fitness(exponent),
+            WalshFaster(exponent) => oa.walsh_faster(exponent),
+            WalshRec(exponent) => oa.walsh_fitness_rec(exponent as f64),
+            Cidev => oa.cidev_fitness(),
+            SheerLuck => oa.sheer_luck_fitness(),
+            Comb(exponent) => oa.comb_fitness(exponent as f64),
+            WalshRestricted(agg) => oa.walsh_restricted(agg),
+            WalshNormalized(exponent) => oa.walsh_faster(exponent) / oa.walsh_normalization(exponent),
+            Custom(custom) => custom.f.fitness(oa),
+        }
+    }
+    fn incremental_fitness(&self, partial: &OArray, last_col: &[bool]) -> f64 {
+        match *self {
+            Walsh(x) => partial.walsh_incremental(x, last_col),
+            WalshFaster(x) => partial.walsh_incremental_faster(x, last_col),
+            DeltaFast => {
+                let mut oa = partial.clone();
+                oa.d.extend_from_slice(last_col);
+                oa.k += 1;
+                oa.delta_incremental_faster()
+            }
+            DeltaP(p) | DeltaNormalized(p) => {
+                let mut oa = partial.clone();
+                oa.d.extend_from_slice(last_col);
+                oa.k += 1;
+                let out = oa.delta_incremental_p(p);
+                match *self {
+                    DeltaNormalized(_) => out / oa.delta_normalization(p),
+                    _ => out,
+                }
+            }
+            Custom(custom) => custom.f.incremental_fitness(partial, last_col),
+            _ => {
+                let mut oa = partial.clone();
+                oa.d.extend_from_slice(last_col);
+                oa.k += 1;
+                oa.fitness()
+            }
+        }
+    }
+}
+
+/// Modo in cui `WalshRestricted` aggrega i coefficienti di Walsh
+/// delle combinazioni di 1..=t colonne
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum WalshAggregation {
+    /// massimo dei valori assoluti
+    Max,
+    /// somma dei valori assoluti elevati all'esponente dato
+    Sum(u32),
+    /// numero di coefficienti non nulli
+    NonZero,
+}
+
 impl OArray {
     fn delta_fitness(&self) -> f64 {
         let mut comb = Combinations::new(self.k, self.target_t);
         let asd: f64 = comb
             .stream_iter()
-            .map(|igrande| self.delta_grande(&igrande, 2.0))
+            .map(|igrande| self.delta_grande(igrande, 2.0))
             .cloned()
             .sum();
         -asd
@@ -30,22 +212,41 @@ impl OArray {
         let mut comb = Combinations::new(self.k, self.target_t);
         let asd: f64 = comb
             .stream_iter()
-            .map(|igrande| self.delta_grande_faster(&igrande, 2))
+            .map(|igrande| self.delta_grande_faster(igrande, 2))
             .cloned()
             .sum();
         -asd
     }
+    /// Come `delta_fitness_fast`, con la norma `p` al posto della norma 2
+    fn delta_fitness_p(&self, p: u32) -> f64 {
+        let mut comb = Combinations::new(self.k, self.target_t);
+        let asd: f64 = comb
+            .stream_iter()
+            .map(|igrande| self.delta_grande_faster(igrande, p))
+            .cloned()
+            .sum();
+        -asd
+    }
+    /// Valore massimo di `-delta_fitness_p(p)`: C(k, t) volte il difetto
+    /// massimo di un insieme di t colonne, che si ha quando tutte le righe
+    /// sono uguali
+    pub(crate) fn delta_normalization(&self, p: u32) -> f64 {
+        let t = self.target_t;
+        let lambda = (self.ngrande >> t) as f64;
+        let strings = f64::from(2u32.pow(t));
+        let max_defect = ((self.ngrande as f64 - lambda).powi(p as i32)
+            + (strings - 1.0) * lambda.powi(p as i32))
+        .powf(1.0 / f64::from(p));
+        binomial(self.k, t as usize) as f64 * max_defect
+    }
+    /// Valore massimo di `-walsh_faster(exp)`: tutti i coefficienti di peso
+    /// 1..=t valgono N in modulo
+    pub(crate) fn walsh_normalization(&self, exp: u32) -> f64 {
+        let count: i64 = (1..=self.target_t as usize).map(|w| binomial(self.k, w)).sum();
+        count as f64 * (self.ngrande as f64).powi(exp as i32)
+    }
     pub fn fitness(&self) -> f64 {
-        let ret = match self.fitness_f {
-            Delta => self.delta_fitness(),
-            DeltaFast => self.delta_fitness_fast(),
-            Walsh(exponent) => self.walsh_fitness(exponent),
-            WalshFaster(exponent) => self.walsh_faster(exponent),
-            WalshRec(exponent) => self.walsh_fitness_rec(exponent as f64),
-            Cidev => self.cidev_fitness(),
-            SheerLuck => self.sheer_luck_fitness(),
-            Comb(exponent) => self.comb_fitness(exponent as f64),
-        };
+        let ret = self.fitness_f.fitness(self);
         //dbg!(ret);
         debug_assert!(ret <= 0.0, "overflow");
         ret
@@ -134,6 +335,46 @@ impl OArray {
         });
         -grand_tot as f64
     }
+    /// Colonne dell'array compattate in parole da 64 bit
+    pub(crate) fn packed_cols(&self) -> Vec<Vec<u64>> {
+        self.iter_cols()
+            .map(|col| {
+                col.chunks(64)
+                    .map(|chunk| {
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .fold(0u64, |acc, (i, &b)| acc | (b as u64) << i)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+    /// Walsh ristretto alle sole combinazioni di 1..=t colonne: ogni coefficiente
+    /// è calcolato con lo XOR delle colonne compattate, senza tabelle di 2^k elementi
+    #[inline(never)]
+    fn walsh_restricted(&self, agg: WalshAggregation) -> f64 {
+        let cols = self.packed_cols();
+        let ngrande = self.ngrande as i64;
+        let mut acc = 0.0;
+        let tmp0 = vec![0u64; cols.first().map_or(0, |c| c.len())];
+        combinations_descent(self.k, self.target_t as usize, 0, &tmp0, &mut |i, tmp| {
+            let tmp1: Vec<u64> = cols[i].iter().zip(tmp.iter()).map(|(a, b)| a ^ b).collect();
+            let ones: i64 = tmp1.iter().map(|w| i64::from(w.count_ones())).sum();
+            let coeff = ((ngrande - 2 * ones).abs()) as f64;
+            match agg {
+                WalshAggregation::Max => acc = f64::max(acc, coeff),
+                WalshAggregation::Sum(p) => acc += coeff.powi(p as i32),
+                WalshAggregation::NonZero => {
+                    if coeff != 0.0 {
+                        acc += 1.0
+                    }
+                }
+            }
+            tmp1
+        });
+        -acc
+    }
     fn walsh_fitness_rec(&self, p: f64) -> f64 {
         let k = self.k;
         -recurse_comb(
@@ -146,14 +387,11 @@ impl OArray {
         )
     }
     fn cidev_fitness(&self) -> f64 {
-        let tt = self.truth_table();
-        let ptt = PolarTruthTable::from(&tt);
-        let wtf = ptt.walsh_tform();
-        let cidev = wtf.cidev(self.target_t as usize);
+        let cidev = self.cidev(self.target_t as usize);
         -f64::from(cidev)
     }
     fn sheer_luck_fitness(&self) -> f64 {
-        if self.delta_fitness_fast() > -std::f64::EPSILON {
+        if self.delta_fitness_fast() > -f64::EPSILON {
             0.0
         } else {
             -1.0
@@ -161,7 +399,7 @@ impl OArray {
     }
     fn comb_fitness(&self, p: f64) -> f64 {
         let a = -self.walsh_fitness_rec(2.0);
-        let b = PolarTruthTable::from(&self.truth_table()).walsh_tform().nonlinearity();
+        let b = self.walsh_tform().nonlinearity();
         -(a + f64::max(0.0, p * (self.k as f64)-1.0-b))
     }
 }
@@ -216,7 +454,6 @@ macro_rules! bool_vec {
 #[cfg(test)]
 mod test {
     use rand::thread_rng;
-    use std::f64::EPSILON;
     use FitnessFunction::*;
     use OArray;
     #[test]
@@ -229,9 +466,8 @@ mod test {
     #[test]
     fn check_fast_delta() {
         use rand::thread_rng;
-        use std::f64::EPSILON;
         let mut rng = thread_rng();
-        let error = EPSILON;
+        let error = f64::EPSILON;
         for _ in 0..100 {
             let rand = OArray::new_random_balanced(8, 7, 3, &mut rng, DeltaFast);
             assert!((rand.delta_fitness() - rand.delta_fitness_fast()).abs() < error);
@@ -248,7 +484,7 @@ mod test {
     #[test]
     fn test_walsh() {
         let mut rng = thread_rng();
-        let error = EPSILON;
+        let error = f64::EPSILON;
         for _ in 0..1000 {
             let rand = OArray::new_random_balanced(8, 7, 3, &mut rng, DeltaFast);
             let delta_is_zero = -rand.delta_fitness_fast() < error;
@@ -259,16 +495,104 @@ mod test {
     #[test]
     fn test_walsh_faster() {
         let mut rng = thread_rng();
-        let error = EPSILON;
+        let error = f64::EPSILON;
         for _ in 0..1000 {
             let rand = OArray::new_random_balanced(8, 7, 3, &mut rng, DeltaFast);
             assert!((rand.walsh_faster(2) - rand.walsh_fitness(2)).abs() < error);
         }
     }
     #[test]
+    fn test_walsh_restricted() {
+        use fitness::WalshAggregation;
+        let mut rng = thread_rng();
+        let error = f64::EPSILON;
+        for _ in 0..1000 {
+            let rand = OArray::new_random_balanced(16, 7, 3, &mut rng, DeltaFast);
+            let sum = rand.walsh_restricted(WalshAggregation::Sum(2));
+            assert!((sum - rand.walsh_fitness(2)).abs() < error);
+            let max = rand.walsh_restricted(WalshAggregation::Max);
+            if rand.packed_rows().len() == rand.ngrande {
+                assert!((-2.0 * max - f64::from(rand.cidev(3))).abs() < error);
+            }
+            let nonzero = rand.walsh_restricted(WalshAggregation::NonZero);
+            assert!((nonzero == 0.0) == (sum == 0.0));
+        }
+        let test = OArray::new(4, 2, 2, bool_vec![0, 0, 1, 1, 0, 1, 0, 1], DeltaFast);
+        assert!(test.walsh_restricted(WalshAggregation::Max) == 0.0);
+        //k oltre il limite delle tabelle di verità
+        let big = OArray::new_random_balanced(128, 80, 2, &mut rng, DeltaFast);
+        assert!((big.walsh_restricted(WalshAggregation::Sum(2)) - big.walsh_faster(2)).abs() < error);
+    }
+    #[test]
+    fn test_normalized() {
+        use fitness::Fitness;
+        use rand::Rng;
+        let mut rng = thread_rng();
+        for _ in 0..50 {
+            let mut oa = OArray::new_random_balanced(16, 7, 3, &mut rng, DeltaFast);
+            let fast = oa.fitness();
+            oa.fitness_f = DeltaP(2);
+            assert!((oa.fitness() - fast).abs() < 1e-9);
+            for &f in &[DeltaNormalized(1), DeltaNormalized(3), WalshNormalized(2)] {
+                oa.fitness_f = f;
+                let fit = oa.fitness();
+                assert!((-1.0..=0.0).contains(&fit), "{:?}: {}", f, fit);
+            }
+        }
+        let constant = OArray::new(16, 5, 2, vec![false; 16 * 5], DeltaNormalized(3));
+        assert!((constant.fitness() + 1.0).abs() < 1e-9);
+        let constant = OArray::new(16, 5, 2, vec![false; 16 * 5], WalshNormalized(2));
+        assert!((constant.fitness() + 1.0).abs() < 1e-9);
+
+        let partial = OArray::generate_partial(16, 3, DeltaNormalized(3));
+        let mut last: Vec<bool> = (0..16).map(|i| i % 2 == 0).collect();
+        rng.shuffle(&mut last);
+        let mut oa = partial.clone();
+        oa.d.extend_from_slice(&last);
+        oa.k += 1;
+        let inc = partial.fitness_f.incremental_fitness(&partial, &last);
+        assert!((oa.fitness() - inc).abs() < 1e-9);
+    }
+    #[test]
+    fn test_custom_fitness() {
+        use fitness::{fitness_by_name, fitness_names, register_fitness, Fitness, FitnessFunction};
+        struct Ones(u32);
+        impl Fitness for Ones {
+            fn fitness(&self, oa: &OArray) -> f64 {
+                -f64::from(self.0) * oa.d.iter().filter(|&&b| b).count() as f64
+            }
+        }
+        assert!(fitness_by_name("Ones", 3).is_none());
+        register_fitness("Ones", |exp| Box::new(Ones(exp)));
+        let f = fitness_by_name("Ones", 3).unwrap();
+        assert!(format!("{:?}", f) == "Custom(Ones)");
+        assert!(f == fitness_by_name("Ones", 3).unwrap());
+        //la stessa istanza viene riusata a ogni ricerca
+        let instance = |f: FitnessFunction| match f {
+            Custom(c) => c.f as *const dyn Fitness as *const u8,
+            _ => unreachable!(),
+        };
+        assert!(instance(f) == instance(fitness_by_name("Ones", 3).unwrap()));
+        assert!(instance(f) != instance(fitness_by_name("Ones", 2).unwrap()));
+        let mut rng = thread_rng();
+        let mut oa = OArray::new_random_balanced(8, 4, 2, &mut rng, f);
+        assert!(oa.fitness() == -3.0 * 16.0);
+        let mut partial = oa.clone();
+        let last: Vec<bool> = partial.d.split_off(8 * 3);
+        partial.k -= 1;
+        assert!(f.incremental_fitness(&partial, &last) == oa.fitness());
+        oa.fitness_f = fitness_by_name("WalshFast", 2).unwrap();
+        assert!(oa.fitness_f == WalshFaster(2));
+        assert!(fitness_by_name("Nope", 2).is_none());
+        //ogni nome elencato è accettato, comprese le fitness registrate
+        let names = fitness_names();
+        assert!(names.contains(&"WalshRestrictedNonZero") && names.contains(&"Ones"));
+        assert!(names.iter().all(|name| fitness_by_name(name, 2).is_some()));
+    }
+    #[test]
     fn test_paper_impl() {
         let mut rng = thread_rng();
-        let error = EPSILON;
+        let error = f64::EPSILON;
         for _ in 0..1000 {
             let rand = OArray::new_random_balanced(16, 15, 3, &mut rng, DeltaFast);
             let a = rand.walsh_fitness_rec(2.0);
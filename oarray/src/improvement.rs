@@ -0,0 +1,371 @@
+//! Hill climbing sullo spettro di Walsh della tabella di verità dell'array.
+//! La tabella è un multiinsieme di righe, quindi le righe ripetute sono
+//! ammesse. Un insieme di miglioramento è formato da scambi (una copia di
+//! una riga tolta, una riga aggiunta): dato che
+//! W(omega) = -2 * sum_x c(x) * (-1)^(omega . x), con c(x) le molteplicità,
+//! ogni scambio cambia W(omega) di 2 * ((-1)^(omega . tolta) - (-1)^(omega . aggiunta)),
+//! quindi le variazioni di cidev e della fitness Walsh(2) si calcolano
+//! esattamente prima di applicare l'insieme, per qualsiasi livello dello spettro.
+
+use fitness::FitnessFunction;
+use oarray::OArray;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use streaming_iterator::StreamingIterator;
+use t_combinations::Combinations;
+use wtform::{rows_to_oarray, walsh_low_weight};
+
+/// Scambi singoli, tra i migliori, combinati per cercare insiemi più grandi
+const BEAM: usize = 24;
+
+/// Massimo numero di scambi singoli (righe distinte per 2^k) esplorati da
+/// `find_improvement`: oltre, la ricerca rinuncia
+const MAX_CANDIDATES: u64 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImprovementSet {
+    /// coppie (riga tolta, riga aggiunta), codificate come in `packed_rows`
+    pub swaps: Vec<(u64, u64)>,
+    /// variazione di cidev(t)
+    pub cidev_delta: i64,
+    /// variazione della fitness Walsh(2)
+    pub fitness_delta: f64,
+}
+
+impl ImprovementSet {
+    /// La fitness Walsh(2) cresce e cidev non peggiora
+    pub fn improves(&self) -> bool {
+        self.fitness_delta > 0.0 && self.cidev_delta <= 0
+    }
+}
+
+/// Tabella di verità a molteplicità intere di un array, con il suo spettro
+/// di Walsh ristretto ai pesi 1..=t
+#[derive(Debug, Clone)]
+pub struct WalshClimber {
+    k: usize,
+    /// righe dell'array, ripetizioni comprese, ordinate
+    rows: Vec<u64>,
+    omegas: Vec<u64>,
+    spectrum: Vec<i64>,
+}
+
+fn sign(omega: u64, x: u64) -> i64 {
+    if (omega & x).count_ones() & 1 == 1 {
+        -1
+    } else {
+        1
+    }
+}
+
+fn cidev_of(spectrum: &[i64]) -> i64 {
+    spectrum.iter().map(|w| w.abs()).max().unwrap_or(0)
+}
+
+fn walsh_fitness_of(spectrum: &[i64]) -> f64 {
+    -spectrum.iter().map(|w| (w / 2) * (w / 2)).sum::<i64>() as f64
+}
+
+impl WalshClimber {
+    /// `None` se l'array è troppo largo
+    pub fn new(oa: &OArray) -> Option<Self> {
+        if oa.k >= 64 {
+            return None;
+        }
+        let rows = oa.packed_rows_multiset();
+        let (omegas, spectrum) = walsh_low_weight(&rows, oa.k, oa.target_t as usize)
+            .into_iter()
+            .unzip();
+        Some(WalshClimber {
+            k: oa.k,
+            rows,
+            omegas,
+            spectrum,
+        })
+    }
+
+    pub fn rows(&self) -> &[u64] {
+        &self.rows
+    }
+
+    pub fn cidev(&self) -> u32 {
+        cidev_of(&self.spectrum) as u32
+    }
+
+    /// Uguale alla fitness `Walsh(2)` dell'array
+    pub fn walsh_fitness(&self) -> f64 {
+        walsh_fitness_of(&self.spectrum)
+    }
+
+    fn spectrum_after(&self, swaps: &[(u64, u64)]) -> Vec<i64> {
+        self.omegas
+            .iter()
+            .zip(self.spectrum.iter())
+            .map(|(&omega, &w)| {
+                w + 2 * swaps
+                    .iter()
+                    .map(|&(removed, added)| sign(omega, removed) - sign(omega, added))
+                    .sum::<i64>()
+            })
+            .collect()
+    }
+
+    /// Variazioni esatte dovute agli scambi, senza applicarli né costruire
+    /// lo spettro risultante.
+    /// Ogni riga tolta deve comparire nell'array almeno quante volte è tolta.
+    pub fn evaluate(&self, swaps: &[(u64, u64)]) -> ImprovementSet {
+        let (cidev_delta, fitness_delta) = self.deltas(swaps);
+        ImprovementSet {
+            swaps: swaps.to_vec(),
+            cidev_delta,
+            fitness_delta,
+        }
+    }
+
+    /// Variazioni di cidev e della fitness, come in `evaluate`
+    fn deltas(&self, swaps: &[(u64, u64)]) -> (i64, f64) {
+        let mut cidev = 0;
+        let mut squares = 0;
+        for (&omega, &w) in self.omegas.iter().zip(self.spectrum.iter()) {
+            let after = w + 2 * swaps
+                .iter()
+                .map(|&(removed, added)| sign(omega, removed) - sign(omega, added))
+                .sum::<i64>();
+            cidev = cidev.max(after.abs());
+            squares += (after / 2) * (after / 2);
+        }
+        (
+            cidev - cidev_of(&self.spectrum),
+            -squares as f64 - self.walsh_fitness(),
+        )
+    }
+
+    /// Cerca il miglior insieme di miglioramento con un solo scambio; se non
+    /// esiste, combina da 2 fino a `max_swaps` scambi scelti tra i `BEAM`
+    /// singoli migliori, restituendo il miglior insieme della dimensione
+    /// più piccola che migliora. Gli scambi singoli sono valutati uno alla
+    /// volta senza conservarli; `None` anche se sono più di `MAX_CANDIDATES`.
+    pub fn find_improvement(&self, max_swaps: usize) -> Option<ImprovementSet> {
+        let mut distinct = self.rows.clone();
+        distinct.dedup();
+        let all = 1u64 << self.k;
+        if (distinct.len() as u64).saturating_mul(all) > MAX_CANDIDATES {
+            return None;
+        }
+        let mut best: Option<ImprovementSet> = None;
+        //il peggiore dei singoli conservati è in cima
+        let mut beam: BinaryHeap<Ranked> = BinaryHeap::with_capacity(BEAM + 1);
+        for &removed in &distinct {
+            for added in (0..all).filter(|&a| a != removed) {
+                let (cidev_delta, fitness_delta) = self.deltas(&[(removed, added)]);
+                let set = || ImprovementSet {
+                    swaps: vec![(removed, added)],
+                    cidev_delta,
+                    fitness_delta,
+                };
+                if fitness_delta > 0.0 && cidev_delta <= 0 {
+                    if best.as_ref().is_none_or(|b| {
+                        cmp_deltas((fitness_delta, cidev_delta), (b.fitness_delta, b.cidev_delta))
+                            == Ordering::Less
+                    }) {
+                        best = Some(set());
+                    }
+                } else if best.is_none() {
+                    let better = |w: &Ranked| {
+                        cmp_deltas((fitness_delta, cidev_delta), (w.0.fitness_delta, w.0.cidev_delta))
+                            == Ordering::Less
+                    };
+                    if beam.len() < BEAM || beam.peek().is_some_and(better) {
+                        beam.push(Ranked(set()));
+                        if beam.len() > BEAM {
+                            beam.pop();
+                        }
+                    }
+                }
+            }
+        }
+        if best.is_some() {
+            return best;
+        }
+        let singles: Vec<ImprovementSet> = beam.into_sorted_vec().into_iter().map(|r| r.0).collect();
+        for size in 2..=max_swaps.min(singles.len()) {
+            let mut best: Option<ImprovementSet> = None;
+            let mut combs = Combinations::new(singles.len(), size as u32);
+            let mut iter = combs.stream_iter();
+            while let Some(comb) = iter.next() {
+                let swaps: Vec<(u64, u64)> = comb.iter().map(|&i| singles[i].swaps[0]).collect();
+                if !self.feasible(&swaps) {
+                    continue;
+                }
+                let set = self.evaluate(&swaps);
+                if set.improves()
+                    && best.as_ref().is_none_or(|b| cmp_best(&set, b) == Ordering::Less)
+                {
+                    best = Some(set);
+                }
+            }
+            if best.is_some() {
+                return best;
+            }
+        }
+        None
+    }
+
+    /// Applica gli scambi dell'insieme
+    pub fn apply(&mut self, set: &ImprovementSet) {
+        self.spectrum = self.spectrum_after(&set.swaps);
+        for &(removed, added) in &set.swaps {
+            //le righe non sono più ordinate dopo il primo scambio
+            let pos = self
+                .rows
+                .iter()
+                .position(|&r| r == removed)
+                .expect("Riga tolta assente dal supporto");
+            self.rows[pos] = added;
+        }
+        self.rows.sort();
+    }
+
+    /// Ogni riga è tolta al più tante volte quante compare, e nessuna
+    /// riga è sia tolta sia aggiunta
+    fn feasible(&self, swaps: &[(u64, u64)]) -> bool {
+        swaps.iter().all(|&(removed, _)| {
+            let times = swaps.iter().filter(|s| s.0 == removed).count();
+            let copies = self.rows.iter().filter(|&&r| r == removed).count();
+            times <= copies && swaps.iter().all(|s| s.1 != removed)
+        })
+    }
+
+    /// Array con le righe della tabella, in ordine
+    pub fn to_oarray(&self, target_t: u32, fitness_f: FitnessFunction) -> OArray {
+        rows_to_oarray(&self.rows, self.k, target_t, fitness_f)
+    }
+}
+
+/// Prima la fitness che cresce di più, poi cidev che cala di più
+fn cmp_best(a: &ImprovementSet, b: &ImprovementSet) -> Ordering {
+    cmp_deltas((a.fitness_delta, a.cidev_delta), (b.fitness_delta, b.cidev_delta))
+}
+
+/// Come `cmp_best`, sulle coppie (variazione della fitness, variazione di cidev)
+fn cmp_deltas(a: (f64, i64), b: (f64, i64)) -> Ordering {
+    b.0.partial_cmp(&a.0)
+        .unwrap_or(Ordering::Equal)
+        .then(a.1.cmp(&b.1))
+}
+
+/// Insieme ordinato con `cmp_best`: in un `BinaryHeap` il peggiore è in cima
+struct Ranked(ImprovementSet);
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for Ranked {}
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_best(&self.0, &other.0)
+    }
+}
+
+impl OArray {
+    /// Applica al più `max_steps` insiemi di miglioramento di al più
+    /// `max_swaps` scambi; restituisce il numero di insiemi applicati.
+    /// Il numero di righe non cambia, anche se ci sono righe ripetute.
+    pub fn walsh_hill_climb(&mut self, max_swaps: usize, max_steps: usize) -> usize {
+        let mut climber = match WalshClimber::new(self) {
+            Some(c) => c,
+            None => return 0,
+        };
+        let mut steps = 0;
+        while steps < max_steps && climber.walsh_fitness() < 0.0 {
+            match climber.find_improvement(max_swaps) {
+                Some(set) => climber.apply(&set),
+                None => break,
+            }
+            steps += 1;
+        }
+        if steps > 0 {
+            *self = climber.to_oarray(self.target_t, self.fitness_f);
+        }
+        steps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::thread_rng;
+    use wtform::PolarTruthTable;
+
+    #[test]
+    fn test_climber() {
+        let mut r = thread_rng();
+        let cidev = |oa: &OArray| {
+            PolarTruthTable::from(&oa.multiset_truth_table())
+                .walsh_tform()
+                .cidev(2)
+        };
+        for _ in 0..20 {
+            let oa =
+                OArray::new_random_balanced(16, 6, 2, &mut r, FitnessFunction::WalshFaster(2));
+            let mut climber = WalshClimber::new(&oa).unwrap();
+            assert!(climber.walsh_fitness() == oa.fitness());
+            assert!(climber.cidev() == cidev(&oa));
+            for _ in 0..3 {
+                let set = match climber.find_improvement(2) {
+                    Some(set) => set,
+                    None => break,
+                };
+                assert!(set.improves());
+                let (old_f, old_c) = (climber.walsh_fitness(), climber.cidev() as i64);
+                climber.apply(&set);
+                //le variazioni previste sono quelle reali
+                let new = climber.to_oarray(2, FitnessFunction::WalshFaster(2));
+                assert!(new.fitness() == old_f + set.fitness_delta);
+                assert!(cidev(&new) as i64 == old_c + set.cidev_delta);
+                assert!(climber.walsh_fitness() == new.fitness());
+            }
+        }
+    }
+
+    #[test]
+    fn test_walsh_hill_climb() {
+        let mut r = thread_rng();
+        for _ in 0..20 {
+            let mut oa =
+                OArray::new_random_balanced(8, 4, 2, &mut r, FitnessFunction::WalshFaster(2));
+            let before = oa.fitness();
+            oa.walsh_hill_climb(2, 10);
+            assert!(oa.fitness() >= before);
+        }
+        //righe ripetute: 00, 00, 11, 11 diventa un OA di forza 2 con due scambi
+        let d = vec![false, false, true, true, false, false, true, true];
+        let mut rep = OArray::new(4, 2, 2, d, FitnessFunction::WalshFaster(2));
+        let climber = WalshClimber::new(&rep).unwrap();
+        assert!(climber.walsh_fitness() == rep.fitness());
+        assert!(!climber.feasible(&[(0, 1), (0, 2), (0, 3)]));
+        assert!(climber.feasible(&[(0, 1), (0, 2)]));
+        assert!(!climber.feasible(&[(0, 3), (3, 1)]));
+        assert!(rep.walsh_hill_climb(2, 10) == 2);
+        assert!(rep.fitness() == 0.0 && rep.ngrande == 4);
+        assert!(sign(321, 552) == 1 && sign(321, 553) == -1);
+    }
+
+    #[test]
+    fn test_candidate_cap() {
+        let mut r = thread_rng();
+        //16 righe per 2^30 scambi singoli: troppi, la ricerca rinuncia subito
+        let mut oa = OArray::new_random_balanced(16, 30, 2, &mut r, FitnessFunction::WalshFaster(2));
+        let climber = WalshClimber::new(&oa).unwrap();
+        assert!(climber.find_improvement(2).is_none());
+        let before = oa.clone();
+        assert!(oa.walsh_hill_climb(2, 10) == 0 && oa == before);
+    }
+}
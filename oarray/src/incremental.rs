@@ -26,6 +26,10 @@ impl OArray {
      * self: a valid OA except for the last column 
      * */
     pub fn delta_incremental_faster(&self) -> f64 {
+        self.delta_incremental_p(2)
+    }
+    /// Come `delta_incremental_faster`, con la norma `p`
+    pub fn delta_incremental_p(&self, p: u32) -> f64 {
         let mut comb = t_combinations::Combinations::new(self.k-1, self.target_t - 1);
         let out: f64 = comb
             .stream_iter()
@@ -33,7 +37,7 @@ impl OArray {
                 let mut my_igrande = Vec::new();
                 my_igrande.push(self.k-1);
                 my_igrande.extend_from_slice(igrande);
-                self.delta_grande_faster(&my_igrande, 2)
+                self.delta_grande_faster(&my_igrande, p)
                 })
             .cloned()
             .sum();
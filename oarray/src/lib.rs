@@ -5,10 +5,22 @@ pub mod oarray;
 mod fitness;
 pub mod t_combinations;
 pub use oarray::OArray;
-pub use fitness::FitnessFunction;
-mod parse;
+pub use fitness::{
+    fitness_by_name, fitness_names, register_fitness, CustomFitness, Fitness, FitnessConstructor, FitnessFunction,
+    WalshAggregation,
+};
+pub mod parse;
+pub use parse::{open_oarrays, OArrayReader};
 pub mod incremental;
 pub mod wtform;
 pub mod binary_strings;
 pub mod features;
+pub mod bounds;
+pub mod anf;
+pub mod cache;
+pub use cache::FitnessCache;
+pub mod improvement;
+pub use improvement::{ImprovementSet, WalshClimber};
+pub mod local_search;
+pub use local_search::{LocalSearch, LocalSearchResult};
 //pub mod isomorf;
\ No newline at end of file
@@ -0,0 +1,141 @@
+//! Ricerca locale tramite scambi di due celle diverse della stessa colonna:
+//! ogni mossa conserva il bilanciamento.
+
+use improvement::{ImprovementSet, WalshClimber};
+use oarray::OArray;
+use rand::Rng;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalSearch {
+    /// esplora le mosse in ordine casuale e applica la prima che migliora
+    FirstImprovement,
+    /// valuta tutte le mosse e applica la migliore
+    Steepest,
+}
+
+impl FromStr for LocalSearch {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(LocalSearch::FirstImprovement),
+            "steepest" => Ok(LocalSearch::Steepest),
+            _ => Err(format!("Unknown local search {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalSearchResult {
+    /// fitness `Walsh(2)` finale
+    pub fitness: f64,
+    /// mosse applicate
+    pub steps: usize,
+    /// scambi valutati
+    pub evaluations: usize,
+}
+
+impl OArray {
+    /// Applica al più `max_steps` scambi migliorativi secondo `strategy`.
+    /// Ogni scambio cambia due righe, quindi è valutato con la variazione
+    /// esatta dello spettro di Walsh di peso 1..=t (vedi `WalshClimber`)
+    /// invece di ricalcolare la fitness: la ricerca migliora la fitness
+    /// `Walsh(2)`, che vale 0 esattamente sugli OA. Si ferma prima se nessuno
+    /// scambio migliora o se la fitness arriva a 0; `None` se l'array è troppo largo.
+    pub fn local_search(
+        &mut self,
+        strategy: LocalSearch,
+        max_steps: usize,
+        rng: &mut impl Rng,
+    ) -> Option<LocalSearchResult> {
+        let mut climber = WalshClimber::new(self)?;
+        let ngrande = self.ngrande;
+        let k = self.k;
+        //righe nell'ordine dell'array, codificate come in `packed_rows`
+        let mut rows: Vec<u64> = (0..ngrande)
+            .map(|i| self.iter_cols().fold(0u64, |acc, col| acc << 1 | col[i] as u64))
+            .collect();
+        let mut evaluations = 0;
+        let mut steps = 0;
+        let mut cols: Vec<usize> = (0..k).collect();
+        while steps < max_steps && climber.walsh_fitness() < 0.0 {
+            let mut best: Option<(usize, usize, usize, ImprovementSet)> = None;
+            rng.shuffle(&mut cols);
+            'search: for &col in &cols {
+                let bit = 1 << (k - 1 - col);
+                let mut zeros: Vec<usize> = (0..ngrande).filter(|&i| rows[i] & bit == 0).collect();
+                let mut ones: Vec<usize> = (0..ngrande).filter(|&i| rows[i] & bit != 0).collect();
+                rng.shuffle(&mut zeros);
+                rng.shuffle(&mut ones);
+                for &i in &zeros {
+                    for &j in &ones {
+                        let set = climber.evaluate(&[(rows[i], rows[i] ^ bit), (rows[j], rows[j] ^ bit)]);
+                        evaluations += 1;
+                        if set.fitness_delta > best.as_ref().map_or(0.0, |b| b.3.fitness_delta) {
+                            best = Some((col, i, j, set));
+                            if strategy == LocalSearch::FirstImprovement {
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+            match best {
+                Some((col, i, j, set)) => {
+                    climber.apply(&set);
+                    let bit = 1 << (k - 1 - col);
+                    rows[i] ^= bit;
+                    rows[j] ^= bit;
+                    self.d.swap(col * ngrande + i, col * ngrande + j);
+                    steps += 1;
+                }
+                None => break,
+            }
+        }
+        Some(LocalSearchResult {
+            fitness: climber.walsh_fitness(),
+            steps,
+            evaluations,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fitness::FitnessFunction;
+    use rand::thread_rng;
+
+    fn is_balanced(oa: &OArray) -> bool {
+        oa.iter_cols()
+            .all(|c| 2 * c.iter().filter(|&&x| x).count() == c.len())
+    }
+
+    #[test]
+    fn test_local_search() {
+        let mut r = thread_rng();
+        for &strategy in &[LocalSearch::FirstImprovement, LocalSearch::Steepest] {
+            for _ in 0..10 {
+                let mut oa =
+                    OArray::new_random_balanced(8, 4, 2, &mut r, FitnessFunction::WalshFaster(2));
+                let start = oa.fitness();
+                let res = oa.local_search(strategy, 3, &mut r).unwrap();
+                assert!(res.steps <= 3);
+                assert!(res.fitness >= start);
+                //la fitness prevista dallo spettro è quella dell'array
+                assert!(res.fitness == oa.fitness());
+                assert!(is_balanced(&oa));
+                //con zero passi l'array non cambia
+                let before = oa.clone();
+                let res = oa.local_search(strategy, 0, &mut r).unwrap();
+                assert!(res.steps == 0 && res.evaluations == 0 && oa == before);
+            }
+        }
+        //un OA valido è già un ottimo
+        let mut oa = OArray::generate_partial(8, 3, FitnessFunction::DeltaFast);
+        let res = oa.local_search(LocalSearch::Steepest, 10, &mut r).unwrap();
+        assert!(res.steps == 0 && res.fitness == 0.0);
+        assert!("steepest".parse::<LocalSearch>() == Ok(LocalSearch::Steepest));
+        assert!("best".parse::<LocalSearch>().is_err());
+    }
+}
@@ -1,29 +1,39 @@
 use fitness::FitnessFunction;
 use oarray::OArray;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines};
+use std::str::FromStr;
 
-impl<'a> From<&'a str> for OArray {
-    fn from(input: &'a str) -> Self {
+/// Legge un array con una riga per riga dell'array e le celle separate
+/// da spazi
+impl FromStr for OArray {
+    type Err = String;
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
         let rows = input.trim().lines();
         let mut d = Vec::new();
         let mut k = None;
         let mut row_cnt = 0;
         for row in rows {
-            let mut nums: Vec<bool> = row
+            let mut nums = row
                 .split_whitespace()
                 .map(|i| match i {
-                    "0" => false,
-                    "1" => true,
-                    _ => panic!("Unexpected char"),
+                    "0" => Ok(false),
+                    "1" => Ok(true),
+                    _ => Err(format!("Unexpected char {:?}", i)),
                 })
-                .collect();
+                .collect::<Result<Vec<bool>, String>>()?;
             if k.is_some() && nums.len() != k.unwrap() {
-                panic!("Uneven column number across lines.");
+                return Err("Uneven column number across lines.".to_string());
             }
             k = Some(nums.len());
             d.append(&mut nums);
             row_cnt += 1;
         }
-        let k = k.unwrap();
+        let k = match k {
+            Some(k) if k > 0 => k,
+            _ => return Err("Empty array".to_string()),
+        };
 
         //Transform to col-major mode
         let mut d1 = Vec::with_capacity(row_cnt * k);
@@ -32,9 +42,86 @@ impl<'a> From<&'a str> for OArray {
                 d1.push(d[k * i + j]);
             }
         }
-        OArray::new(row_cnt, k, 1, d1, FitnessFunction::DeltaFast)
+        Ok(OArray::new(row_cnt, k, 1, d1, FitnessFunction::DeltaFast))
+    }
+}
+
+impl<'a> From<&'a str> for OArray {
+    fn from(input: &'a str) -> Self {
+        input.parse().unwrap_or_else(|e: String| panic!("{}", e))
     }
 }
+
+/// Legge una sequenza di array separati da righe vuote o da marcatori di
+/// documento (`---` o `...`), uno alla volta
+pub struct OArrayReader<R> {
+    lines: Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> OArrayReader<R> {
+    pub fn new(input: R) -> Self {
+        OArrayReader {
+            lines: input.lines(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for OArrayReader<R> {
+    type Item = io::Result<OArray>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = String::new();
+        let mut first_line = 0;
+        for line in &mut self.lines {
+            self.line += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    //l'array letto fin qui è incompleto: lo si segnala insieme all'errore
+                    let context = if buffer.is_empty() {
+                        format!("line {}", self.line)
+                    } else {
+                        format!("array starting at line {}, line {}", first_line, self.line)
+                    };
+                    return Some(Err(io::Error::new(e.kind(), format!("{}: {}", context, e))));
+                }
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("---") || trimmed.starts_with("...") {
+                if buffer.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if buffer.is_empty() {
+                first_line = self.line;
+            }
+            buffer.push_str(trimmed);
+            buffer.push('\n');
+        }
+        if buffer.is_empty() {
+            return None;
+        }
+        Some(buffer.parse().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("array starting at line {}: {}", first_line, e),
+            )
+        }))
+    }
+}
+
+/// Apre un file di array, o lo standard input se `path` è `-`
+pub fn open_oarrays(path: &OsStr) -> io::Result<OArrayReader<Box<dyn BufRead>>> {
+    let input: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    Ok(OArrayReader::new(input))
+}
+
 #[test]
 fn test_parse() {
     let string = "0 0 0
@@ -63,3 +150,35 @@ fn test_parse3() {
                   1 0 0";
     let _ = OArray::from(string);
 }
+
+#[test]
+fn test_reader() {
+    let input = "0 0
+1 1
+
+---
+0 1
+1 0
+...
+
+
+0 0
+1 2
+1 1
+";
+    let oas: Vec<io::Result<OArray>> = OArrayReader::new(input.as_bytes()).collect();
+    assert!(oas.len() == 3);
+    let first = oas[0].as_ref().unwrap();
+    assert!(first.ngrande == 2 && first.d == vec![false, true, false, true]);
+    assert!(oas[1].as_ref().unwrap().d == vec![false, true, true, false]);
+    let err = oas[2].as_ref().unwrap_err().to_string();
+    assert!(err.contains("line 10"), "{}", err);
+    assert!(OArrayReader::new("\n\n---\n".as_bytes()).next().is_none());
+    //un errore di lettura a metà array riporta la riga
+    let broken: &[u8] = b"0 0\n1 1\n\n0 1\n\xff\n";
+    let oas: Vec<io::Result<OArray>> = OArrayReader::new(broken).collect();
+    assert!(oas[0].is_ok());
+    let err = oas[1].as_ref().unwrap_err().to_string();
+    assert!(err.contains("array starting at line 4, line 5"), "{}", err);
+    assert!("".parse::<OArray>().is_err());
+}
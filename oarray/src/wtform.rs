@@ -1,20 +1,33 @@
-use binary_strings::{hamming_weight, vec2usize, BinaryStringIterator};
+use binary_strings::{hamming_weight, usize_hamming_weight, vec2usize, BinaryStringIterator};
 use fitness::FitnessFunction;
 use oarray::OArray;
 use std::fmt::{Display, Error, Formatter};
+use std::iter::repeat_n;
+use std::ops::{Add, Sub};
+use std::thread;
+use streaming_iterator::StreamingIterator;
+use t_combinations::Combinations;
 
 pub struct TruthTable {
     pub table: Vec<bool>,
     pub log2len: usize,
 }
 
+/// Funzione indicatrice a valori interi: `table[x]` è il numero di righe
+/// uguali a x, per gli array con righe ripetute
+pub struct MultisetTruthTable {
+    pub table: Vec<u32>,
+    pub log2len: usize,
+}
+
 pub struct PolarTruthTable {
-    pub table: Vec<i32>,
+    pub table: Vec<i64>,
     pub log2len: usize,
 }
 
+/// I coefficienti arrivano a 2^k in modulo, quindi servono 64 bit per k >= 31
 pub struct WalshTform {
-    pub table: Vec<i32>,
+    pub table: Vec<i64>,
     pub log2len: usize,
 }
 
@@ -24,7 +37,7 @@ impl TruthTable {
         TruthTable { table, log2len: l }
     }
 }
-impl<'a> From<&'a TruthTable> for PolarTruthTable {
+impl From<&TruthTable> for PolarTruthTable {
     fn from(t: &TruthTable) -> Self {
         let newt = t.table.iter().map(|&i| if i { -1 } else { 1 }).collect();
         PolarTruthTable {
@@ -33,15 +46,44 @@ impl<'a> From<&'a TruthTable> for PolarTruthTable {
         }
     }
 }
+/// Forma polare 1 - 2 * c(x): coincide con quella di `TruthTable` quando
+/// le molteplicità sono 0 o 1, e per omega != 0 la trasformata vale
+/// -2 * sum_x c(x) * (-1)^(omega . x)
+impl From<&MultisetTruthTable> for PolarTruthTable {
+    fn from(t: &MultisetTruthTable) -> Self {
+        let newt = t.table.iter().map(|&c| 1 - 2 * i64::from(c)).collect();
+        PolarTruthTable {
+            table: newt,
+            log2len: t.log2len,
+        }
+    }
+}
 impl PolarTruthTable {
     pub fn walsh_tform(&self) -> WalshTform {
         let mut truth = self.table.clone();
-        walsh_tform_step(&mut truth);
+        fwht(&mut truth);
         WalshTform {
             table: truth,
             log2len: self.log2len,
         }
     }
+    /// Come `walsh_tform`, ma riusa la tabella senza copiarla
+    pub fn into_walsh_tform(mut self) -> WalshTform {
+        fwht(&mut self.table);
+        WalshTform {
+            table: self.table,
+            log2len: self.log2len,
+        }
+    }
+    /// Trasformata calcolata con `threads` thread, per tabelle con almeno
+    /// 2^`PARALLEL_MIN_LOG2LEN` elementi
+    pub fn into_walsh_tform_parallel(mut self, threads: usize) -> WalshTform {
+        fwht_parallel(&mut self.table, threads);
+        WalshTform {
+            table: self.table,
+            log2len: self.log2len,
+        }
+    }
 }
 
 impl Display for TruthTable {
@@ -86,15 +128,147 @@ impl WalshTform {
             .unwrap() as u32
     }
     pub fn nonlinearity(&self) -> f64 {
-        f64::from(2u32.pow((self.log2len - 1) as u32))
-            - f64::from(self.table.iter().map(|x| x.abs()).max().unwrap()) / 2.0
+        2f64.powi(self.log2len as i32 - 1)
+            - self.table.iter().map(|x| x.abs()).max().unwrap() as f64 / 2.0
     }
-    pub fn radius(&self) -> i32 {
+    pub fn radius(&self) -> i64 {
         self.table.iter().map(|i| i.abs()).max().unwrap()
     }
+    pub fn is_balanced(&self) -> bool {
+        self.table[0] == 0
+    }
+    /// Ordine di immunità alla correlazione: massimo m tale che W(omega) = 0
+    /// per ogni omega di peso 1 <= w(omega) <= m
+    pub fn correlation_immunity(&self) -> usize {
+        max_zero_weight(&self.table, self.log2len)
+    }
+    /// Ordine di resilienza: immunità alla correlazione di una funzione bilanciata
+    pub fn resiliency(&self) -> Option<usize> {
+        if self.is_balanced() {
+            Some(self.correlation_immunity())
+        } else {
+            None
+        }
+    }
+    /// Spettro di autocorrelazione r(a) = sum_x (-1)^(f(x) + f(x + a)),
+    /// calcolato con il teorema di Wiener-Khinchin: r = 2^-n * W(W^2)
+    pub fn autocorrelation(&self) -> Vec<i64> {
+        let mut squares: Vec<i64> = self.table.iter().map(|&w| w * w).collect();
+        fwht(&mut squares);
+        let len = squares.len() as i64;
+        squares.iter().map(|&x| x / len).collect()
+    }
+    /// Ordine del criterio di propagazione: massimo l tale che r(a) = 0
+    /// per ogni a di peso 1 <= w(a) <= l
+    pub fn propagation_criterion(&self) -> usize {
+        max_zero_weight(&self.autocorrelation(), self.log2len)
+    }
+    /// Indicatore somma dei quadrati: sum_a r(a)^2
+    pub fn sum_of_squares(&self) -> i64 {
+        self.autocorrelation()
+            .iter()
+            .map(|&r| r * r)
+            .sum()
+    }
+    /// Indicatore assoluto: max_{a != 0} |r(a)|
+    pub fn absolute_indicator(&self) -> i64 {
+        self.autocorrelation()
+            .iter()
+            .skip(1)
+            .map(|r| r.abs())
+            .max()
+            .unwrap_or(0)
+    }
+    /// Una funzione è bent se |W(omega)| = 2^(n/2) per ogni omega
+    pub fn is_bent(&self) -> bool {
+        if self.log2len & 1 == 1 {
+            return false;
+        }
+        let level = 1 << (self.log2len / 2);
+        self.table.iter().all(|w| w.abs() == level)
+    }
+}
+
+/// Massimo m tale che `spectrum` è nullo su tutti gli indici di peso 1..=m
+fn max_zero_weight(spectrum: &[i64], log2len: usize) -> usize {
+    spectrum
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(_, &val)| val != 0)
+        .map(|(index, _)| usize_hamming_weight(index) - 1)
+        .min()
+        .unwrap_or(log2len)
+}
+
+impl TruthTable {
+    /// Immunità algebrica: grado minimo di una funzione non nulla g tale che
+    /// g * f = 0 oppure g * (f + 1) = 0
+    pub fn algebraic_immunity(&self) -> usize {
+        let ones: Vec<usize> = (0..self.table.len()).filter(|&x| self.table[x]).collect();
+        let zeros: Vec<usize> = (0..self.table.len()).filter(|&x| !self.table[x]).collect();
+        (0..self.log2len)
+            .find(|&d| {
+                has_annihilator(&ones, self.log2len, d) || has_annihilator(&zeros, self.log2len, d)
+            })
+            .unwrap_or(self.log2len)
+    }
+}
+
+/// Controlla se esiste una funzione non nulla di grado <= `d` che si annulla
+/// su tutti i punti di `support`, cioè se i monomi di grado <= `d`, valutati
+/// sui punti, sono linearmente dipendenti su GF(2)
+fn has_annihilator(support: &[usize], n: usize, d: usize) -> bool {
+    let monomials: Vec<usize> = (0..1usize << n)
+        .filter(|&u| usize_hamming_weight(u) <= d)
+        .collect();
+    if monomials.len() > support.len() {
+        return true;
+    }
+    let words = support.len() / 64 + 1;
+    let mut rows: Vec<Vec<u64>> = monomials
+        .iter()
+        .map(|&u| {
+            let mut row = vec![0u64; words];
+            for (j, &x) in support.iter().enumerate() {
+                if x & u == u {
+                    row[j / 64] |= 1 << (j % 64);
+                }
+            }
+            row
+        })
+        .collect();
+    gf2_rank(&mut rows, support.len()) < monomials.len()
+}
+
+/// Rango su GF(2) di una matrice con righe rappresentate come bitset
+fn gf2_rank(rows: &mut [Vec<u64>], n_cols: usize) -> usize {
+    let mut rank = 0;
+    for col in 0..n_cols {
+        let (word, bit) = (col / 64, 1u64 << (col % 64));
+        let pivot = match (rank..rows.len()).find(|&r| rows[r][word] & bit != 0) {
+            Some(p) => p,
+            None => continue,
+        };
+        rows.swap(rank, pivot);
+        let pivot_row = rows[rank].clone();
+        for (r, row) in rows.iter_mut().enumerate() {
+            if r != rank && row[word] & bit != 0 {
+                for (a, b) in row.iter_mut().zip(pivot_row.iter()) {
+                    *a ^= b;
+                }
+            }
+        }
+        rank += 1;
+        if rank == rows.len() {
+            break;
+        }
+    }
+    rank
 }
 impl OArray {
     pub fn truth_table(&self) -> TruthTable {
+        assert!(self.k < 64, "Tabella di verità troppo grande (k = {})", self.k);
         let l = 2usize.pow(self.k as u32);
         let mut out = vec![false; l];
         for r in self.iter_rows() {
@@ -106,6 +280,38 @@ impl OArray {
         }
         TruthTable::new(out)
     }
+    /// Trasformata di Walsh della tabella di verità; per k >= `PARALLEL_MIN_LOG2LEN`
+    /// è calcolata con un thread per ogni core disponibile
+    pub fn walsh_tform(&self) -> WalshTform {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        PolarTruthTable::from(&self.truth_table()).into_walsh_tform_parallel(threads)
+    }
+    /// Come `truth_table`, ma conta le righe ripetute
+    pub fn multiset_truth_table(&self) -> MultisetTruthTable {
+        assert!(self.k < 64, "Tabella di verità troppo grande (k = {})", self.k);
+        let mut out = vec![0; 1 << self.k];
+        for row in self.packed_rows_multiset() {
+            out[row as usize] += 1;
+        }
+        MultisetTruthTable {
+            table: out,
+            log2len: self.k,
+        }
+    }
+    /// Array con `table[x]` copie della riga x; N è la somma delle molteplicità
+    pub fn from_multiset_truth_table(
+        truth: &MultisetTruthTable,
+        target_t: u32,
+        fitness_f: FitnessFunction,
+    ) -> Self {
+        let rows: Vec<u64> = truth
+            .table
+            .iter()
+            .enumerate()
+            .flat_map(|(ind, &c)| repeat_n(ind as u64, c as usize))
+            .collect();
+        rows_to_oarray(&rows, truth.log2len, target_t, fitness_f)
+    }
     pub fn from_truth_table(
         truth: &TruthTable,
         ngrande: usize,
@@ -130,16 +336,157 @@ impl OArray {
     }
 }
 
-fn walsh_tform_step(v: &mut [i32]) {
-    let half = v.len() / 2;
-    for i in 0..half {
-        let temp = v[i];
-        v[i] += v[i + half];
-        v[i + half] = temp - v[i + half];
+/// Trasformata di Walsh-Hadamard veloce, iterativa e in place.
+/// La lunghezza di `v` deve essere una potenza di 2.
+pub fn fwht<T>(v: &mut [T])
+where
+    T: Copy + Add<Output = T> + Sub<Output = T>,
+{
+    debug_assert!(v.len().is_power_of_two());
+    let mut h = 1;
+    while h < v.len() {
+        for block in v.chunks_mut(2 * h) {
+            let (lo, hi) = block.split_at_mut(h);
+            for (a, b) in lo.iter_mut().zip(hi.iter_mut()) {
+                let (x, y) = (*a, *b);
+                *a = x + y;
+                *b = x - y;
+            }
+        }
+        h *= 2;
     }
-    if half > 1 {
-        walsh_tform_step(&mut v[..half]);
-        walsh_tform_step(&mut v[half..]);
+}
+
+/// Sotto questa dimensione (in log2) `fwht_parallel` usa la versione sequenziale
+pub const PARALLEL_MIN_LOG2LEN: usize = 20;
+
+/// Trasformata di Walsh-Hadamard veloce su `threads` thread.
+/// L'array è visto come una matrice P x C (P = numero di thread, potenza di 2):
+/// prima si trasformano le righe in parallelo, poi le colonne, assegnando
+/// a ogni thread una fetta contigua di colonne.
+pub fn fwht_parallel<T>(v: &mut [T], threads: usize)
+where
+    T: Copy + Send + Add<Output = T> + Sub<Output = T>,
+{
+    let len = v.len();
+    debug_assert!(len.is_power_of_two());
+    let mut p = 1;
+    while p * 2 <= threads {
+        p *= 2;
+    }
+    if p == 1 || len < 1 << PARALLEL_MIN_LOG2LEN || len < p * p {
+        fwht(v);
+        return;
+    }
+    let row_len = len / p;
+    let slice_len = row_len / p;
+    thread::scope(|s| {
+        for row in v.chunks_mut(row_len) {
+            s.spawn(move || fwht(row));
+        }
+    });
+    let mut parts: Vec<Vec<&mut [T]>> = (0..p).map(|_| Vec::with_capacity(p)).collect();
+    for row in v.chunks_mut(row_len) {
+        for (q, piece) in row.chunks_mut(slice_len).enumerate() {
+            parts[q].push(piece);
+        }
+    }
+    thread::scope(|s| {
+        for mut part in parts {
+            s.spawn(move || {
+                let mut h = 1;
+                while h < part.len() {
+                    for i in (0..part.len()).step_by(2 * h) {
+                        for r in i..i + h {
+                            let (lo, hi) = part.split_at_mut(r + h);
+                            for (a, b) in lo[r].iter_mut().zip(hi[0].iter_mut()) {
+                                let (x, y) = (*a, *b);
+                                *a = x + y;
+                                *b = x - y;
+                            }
+                        }
+                    }
+                    h *= 2;
+                }
+            });
+        }
+    });
+}
+
+/// Coefficienti di Walsh W(omega) per tutti gli omega di peso 1..=`t`,
+/// calcolati direttamente dal supporto della funzione (gli indici x con
+/// f(x) = 1) come W(omega) = -2 * sum_{x in supp} (-1)^(omega . x).
+/// Il bit di omega relativo alla variabile i è `1 << (log2len - 1 - i)`.
+/// Restituisce le coppie (omega, W(omega)).
+pub fn walsh_low_weight(support: &[u64], log2len: usize, t: usize) -> Vec<(u64, i64)> {
+    let mut out = Vec::new();
+    for w in 1..=t.min(log2len) {
+        let mut combs = Combinations::new(log2len, w as u32);
+        let mut iter = combs.stream_iter();
+        while let Some(comb) = iter.next() {
+            let omega = comb
+                .iter()
+                .fold(0u64, |acc, &i| acc | 1 << (log2len - 1 - i));
+            let tot: i64 = support
+                .iter()
+                .map(|&x| if (omega & x).count_ones() & 1 == 1 { -1 } else { 1 })
+                .sum();
+            out.push((omega, -2 * tot));
+        }
+    }
+    out
+}
+
+impl TruthTable {
+    /// Spettro di Walsh ristretto agli omega di peso 1..=`t`, vedi `walsh_low_weight`
+    pub fn walsh_low_weight(&self, t: usize) -> Vec<(u64, i64)> {
+        let support: Vec<u64> = (0..self.table.len() as u64)
+            .filter(|&x| self.table[x as usize])
+            .collect();
+        walsh_low_weight(&support, self.log2len, t)
+    }
+}
+
+/// Array con le righe date, codificate come in `packed_rows`
+pub fn rows_to_oarray(rows: &[u64], k: usize, target_t: u32, fitness_f: FitnessFunction) -> OArray {
+    let d: Vec<bool> = (1..=k)
+        .flat_map(|col| rows.iter().map(move |row| row & (1 << (k - col)) != 0))
+        .collect();
+    OArray::new(rows.len(), k, target_t, d, fitness_f)
+}
+
+impl OArray {
+    /// Righe distinte dell'array codificate come interi, con la prima colonna
+    /// nel bit più significativo (come negli indici di `truth_table`)
+    pub fn packed_rows(&self) -> Vec<u64> {
+        let mut rows = self.packed_rows_multiset();
+        rows.dedup();
+        rows
+    }
+    /// Come `packed_rows`, ma conserva le righe ripetute
+    pub fn packed_rows_multiset(&self) -> Vec<u64> {
+        assert!(self.k <= 64);
+        let mut rows: Vec<u64> = (0..self.ngrande)
+            .map(|i| {
+                self.iter_cols()
+                    .fold(0u64, |acc, col| acc << 1 | col[i] as u64)
+            })
+            .collect();
+        rows.sort();
+        rows
+    }
+    /// Spettro di Walsh della tabella di verità ristretto ai pesi 1..=`t`,
+    /// senza costruire la tabella di 2^k elementi
+    pub fn walsh_low_weight(&self, t: usize) -> Vec<(u64, i64)> {
+        walsh_low_weight(&self.packed_rows(), self.k, t)
+    }
+    /// Equivalente a `cidev(t)` sulla trasformata della tabella di verità
+    pub fn cidev(&self, t: usize) -> u32 {
+        self.walsh_low_weight(t)
+            .iter()
+            .map(|(_, w)| w.abs())
+            .max()
+            .unwrap_or(0) as u32
     }
 }
 
@@ -151,8 +498,56 @@ fn test_truth() {
     //let r = OArray::new_random_balanced(8, 4, 2, &mut rng, FitnessFunction::Delta);
     let mut test = [1, -1, -1, 1, -1, 1, 1, -1];
     println!("{:?}", test);
-    println!("{:?}", walsh_tform_step(&mut test));
+    fwht(&mut test);
     println!("{:?}", test);
+    assert!(test == [0, 0, 0, 0, 0, 0, 0, 8]);
+}
+
+#[test]
+fn test_fwht_parallel() {
+    use rand::{thread_rng, Rng};
+    let mut rng = thread_rng();
+    let v: Vec<i64> = (0..1 << PARALLEL_MIN_LOG2LEN)
+        .map(|_| if rng.gen() { 1 } else { -1 })
+        .collect();
+    let mut seq = v.clone();
+    fwht(&mut seq);
+    for &threads in &[2, 3, 4, 8] {
+        let mut par = v.clone();
+        fwht_parallel(&mut par, threads);
+        assert!(par == seq);
+    }
+}
+
+#[test]
+fn test_walsh_tform_parallel() {
+    use rand::thread_rng;
+    let mut rng = thread_rng();
+    let k = PARALLEL_MIN_LOG2LEN;
+    let oa = OArray::new_random_balanced(64, k, 2, &mut rng, FitnessFunction::Comb(1));
+    let seq = PolarTruthTable::from(&oa.truth_table()).walsh_tform();
+    assert!(oa.walsh_tform().table == seq.table);
+}
+
+#[test]
+fn test_low_weight() {
+    use features::binomial;
+    use rand::thread_rng;
+    let mut rng = thread_rng();
+    for _ in 0..100 {
+        let oa = OArray::new_random_balanced(16, 6, 3, &mut rng, FitnessFunction::Delta);
+        let tt = oa.truth_table();
+        let wtf = PolarTruthTable::from(&tt).walsh_tform();
+        for t in 1..=4 {
+            let low = oa.walsh_low_weight(t);
+            assert!(low == tt.walsh_low_weight(t));
+            assert!(low.len() == (1..=t).map(|w| binomial(6, w) as usize).sum::<usize>());
+            for (omega, w) in low {
+                assert!(wtf.table[omega as usize] == w);
+            }
+            assert!(oa.cidev(t) == wtf.cidev(t));
+        }
+    }
 }
 
 #[test]
@@ -169,3 +564,82 @@ fn test_from_truth() {
         }
     }
 }
+
+#[test]
+fn test_multiset() {
+    use rand::thread_rng;
+    let mut rng = thread_rng();
+    for _ in 0..20 {
+        //con 16 righe e 3 colonne ci sono per forza righe ripetute
+        let oa = OArray::new_random_balanced(16, 3, 2, &mut rng, FitnessFunction::WalshFaster(2));
+        let mt = oa.multiset_truth_table();
+        assert!(mt.table.iter().sum::<u32>() == 16);
+        let back = OArray::from_multiset_truth_table(&mt, 2, FitnessFunction::WalshFaster(2));
+        assert!(back.packed_rows_multiset() == oa.packed_rows_multiset());
+        assert!(back.fitness() == oa.fitness());
+        let wtf = PolarTruthTable::from(&mt).walsh_tform();
+        let fit: i64 = (1..8usize)
+            .filter(|&omega| usize_hamming_weight(omega) <= 2)
+            .map(|omega| (wtf.table[omega] / 2).pow(2))
+            .sum();
+        assert!(-fit as f64 == oa.fitness());
+    }
+    //senza ripetizioni coincide con la tabella di verità
+    let oa = OArray::generate_partial(8, 3, FitnessFunction::WalshFaster(2));
+    let set = PolarTruthTable::from(&oa.truth_table()).walsh_tform();
+    let multi = PolarTruthTable::from(&oa.multiset_truth_table()).walsh_tform();
+    assert!(set.table == multi.table);
+}
+
+#[test]
+fn test_crypto_props() {
+    // funzione affine: indicatrice di un OA lineare di forza 2
+    let affine = TruthTable::new((0..8usize).map(|x| usize_hamming_weight(x) & 1 == 0).collect());
+    let wtf = PolarTruthTable::from(&affine).walsh_tform();
+    assert!(wtf.is_balanced());
+    assert!(wtf.correlation_immunity() == 2);
+    assert!(wtf.resiliency() == Some(2));
+    assert!(wtf.autocorrelation().iter().all(|r| r.abs() == 8));
+    assert!(wtf.propagation_criterion() == 0);
+    assert!(wtf.absolute_indicator() == 8);
+    assert!(wtf.sum_of_squares() == 512);
+    assert!(!wtf.is_bent());
+    assert!(affine.algebraic_immunity() == 1);
+
+    // x0*x1 + x2*x3 è bent
+    let bent = TruthTable::new(
+        (0..16usize)
+            .map(|x| (x & 3 == 3) ^ (x & 12 == 12))
+            .collect(),
+    );
+    let wtf = PolarTruthTable::from(&bent).walsh_tform();
+    assert!(wtf.is_bent());
+    assert!(!wtf.is_balanced());
+    assert!(wtf.resiliency().is_none());
+    assert!(wtf.autocorrelation()[0] == 16);
+    assert!(wtf.absolute_indicator() == 0);
+    assert!(wtf.sum_of_squares() == 256);
+    assert!(wtf.propagation_criterion() == 4);
+    assert!(bent.algebraic_immunity() == 2);
+
+    let constant = TruthTable::new(vec![false; 8]);
+    assert!(constant.algebraic_immunity() == 0);
+}
+
+#[test]
+fn test_autocorrelation_direct() {
+    use rand::thread_rng;
+    let mut rng = thread_rng();
+    for _ in 0..20 {
+        let oa = OArray::new_random_balanced(16, 5, 2, &mut rng, FitnessFunction::Delta);
+        let tt = oa.truth_table();
+        let wtf = PolarTruthTable::from(&tt).walsh_tform();
+        let auto = wtf.autocorrelation();
+        for (a, &r) in auto.iter().enumerate() {
+            let direct: i64 = (0..tt.table.len())
+                .map(|x| if tt.table[x] ^ tt.table[x ^ a] { -1 } else { 1 })
+                .sum();
+            assert!(direct == r);
+        }
+    }
+}
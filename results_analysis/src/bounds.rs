@@ -0,0 +1,47 @@
+extern crate clap;
+extern crate oarray;
+
+use clap::{App, Arg};
+
+use oarray::bounds::{check_parameters, delsarte_bound, rao_bound};
+use std::process::exit;
+
+fn main() {
+    let matches = App::new("bounds")
+        .version("0.1.0")
+        .author("Pietro Brenna <p.brenna2@campus.unimib.it>")
+        .about("Checks the Rao and Delsarte LP bounds for OA(N, k, 2, t). Exit status 0: no bound violated; 1: the OA cannot exist")
+        .arg(
+            Arg::with_name("N")
+                .help("N, the height of the OA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("k")
+                .help("the width of the OA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("t")
+                .help("the strength of the OA")
+                .required(true),
+        )
+        .get_matches();
+
+    let ngrande: usize = matches.value_of("N").unwrap().parse().expect("Invalid value for N");
+    let k: usize = matches.value_of("k").unwrap().parse().expect("Invalid value for k");
+    let t: u32 = matches.value_of("t").unwrap().parse().expect("Invalid value for t");
+
+    println!("Rao bound: {}", rao_bound(k, t));
+    println!("Delsarte LP bound: {:.4}", delsarte_bound(k, t));
+    match check_parameters(ngrande, k, t) {
+        Some(violation) => {
+            println!("OA({}, {}, 2, {}) cannot exist: {}", ngrande, k, t, violation);
+            exit(1);
+        }
+        None => {
+            println!("OA({}, {}, 2, {}) not excluded by the bounds", ngrande, k, t);
+            exit(0);
+        }
+    }
+}
@@ -1,23 +1,73 @@
+extern crate clap;
 extern crate oarray;
+extern crate serde;
+extern crate serde_json;
 
+mod record;
+
+use clap::{App, Arg};
 use oarray::wtform::*;
 use oarray::FitnessFunction;
-use oarray::OArray;
-use std::env;
-use std::io::{self, Read};
+use oarray::{open_oarrays, OArray};
+use record::Record;
+use std::io;
 
 fn main() -> io::Result<()> {
-    let mut buffer = String::new();
-    let t: u32 = env::args()
-        .nth(1)
-        .expect("Fornire parametro t come argomento")
+    let matches = App::new("check_boolfun_props")
+        .about("Prints the Boolean function properties of each OA in the input")
+        .arg(
+            Arg::with_name("t")
+                .help("the strength of the OA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("files")
+                .help("OA files, with arrays separated by blank lines or ---. Use `-` for standard input")
+                .multiple(true)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print one JSON record per array"),
+        )
+        .get_matches();
+    let t: u32 = matches
+        .value_of("t")
+        .unwrap()
         .parse()
-        .unwrap();
-    io::stdin().read_to_string(&mut buffer)?;
-    let mut oa = OArray::from(buffer.as_str());
+        .expect("Invalid value for t");
+    let json = matches.is_present("json");
+    let mut index = 0;
+    for f in matches.values_of_os("files").unwrap() {
+        for oa in open_oarrays(f)? {
+            let mut oa = match oa {
+                Ok(oa) => oa,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                    eprintln!("{}: {}", f.to_string_lossy(), e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            oa.target_t = t;
+            if json {
+                println!("{}", record(&mut oa, index));
+            } else {
+                if index > 0 {
+                    println!();
+                }
+                print_props(&mut oa);
+            }
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn print_props(oa: &mut OArray) {
     let truth = oa.truth_table();
     let tform = PolarTruthTable::from(&truth).walsh_tform();
-    oa.target_t = t;
+    let t = oa.target_t;
     println!("Target t: {}", t);
     println!("Array:\n{}", oa);
     oa.fitness_f = FitnessFunction::Delta;
@@ -28,7 +78,50 @@ fn main() -> io::Result<()> {
         println!("cidev({}): {}", i, tform.cidev(i));
     }
     println!("radius: {}", tform.radius());
+    let anf = oa.anf();
+    println!("Algebraic degree: {}", anf.degree());
+    println!("ANF: {}", anf);
+    println!("Nonlinearity: {}", tform.nonlinearity());
+    println!("Balanced: {}", tform.is_balanced());
+    println!("Correlation immunity: {}", tform.correlation_immunity());
+    match tform.resiliency() {
+        Some(r) => println!("Resiliency: {}", r),
+        None => println!("Resiliency: not resilient"),
+    }
+    println!("Propagation criterion: {}", tform.propagation_criterion());
+    println!("Sum-of-squares indicator: {}", tform.sum_of_squares());
+    println!("Absolute indicator: {}", tform.absolute_indicator());
+    println!("Algebraic immunity: {}", truth.algebraic_immunity());
+    println!("Bent: {}", tform.is_bent());
+    println!("Autocorrelation: {:?}", tform.autocorrelation());
     println!("Truth table:\n{}", truth);
     println!("Walsh transform:\n{}", tform);
-    Ok(())
+}
+
+fn record(oa: &mut OArray, index: usize) -> Record {
+    let truth = oa.truth_table();
+    let tform = PolarTruthTable::from(&truth).walsh_tform();
+    let mut r = Record::new();
+    r.field("index", &index)
+        .field("N", &oa.ngrande)
+        .field("k", &oa.k)
+        .field("t", &oa.target_t);
+    oa.fitness_f = FitnessFunction::Delta;
+    r.field("fitness_delta", &oa.fitness());
+    oa.fitness_f = FitnessFunction::Walsh(2);
+    r.field("fitness_walsh2", &oa.fitness());
+    let cidev: Vec<u32> = (1..6).map(|i| tform.cidev(i)).collect();
+    r.field("cidev", &cidev)
+        .field("radius", &tform.radius())
+        .field("algebraic_degree", &truth.anf().degree())
+        .field("nonlinearity", &tform.nonlinearity())
+        .field("balanced", &tform.is_balanced())
+        .field("correlation_immunity", &tform.correlation_immunity())
+        .field("resiliency", &tform.resiliency())
+        .field("propagation_criterion", &tform.propagation_criterion())
+        .field("sum_of_squares", &tform.sum_of_squares())
+        .field("absolute_indicator", &tform.absolute_indicator())
+        .field("algebraic_immunity", &truth.algebraic_immunity())
+        .field("bent", &tform.is_bent());
+    r
 }
@@ -1,42 +1,90 @@
+extern crate clap;
 extern crate oarray;
-use std::env;
+extern crate serde;
+extern crate serde_json;
 
-use oarray::OArray;
-use oarray::wtform::PolarTruthTable;
-use std::io::{self, Read};
+mod record;
 
-fn main() -> io::Result<()>{
-    let mut buffer = String::new();
-    io::stdin().read_to_string(&mut buffer)?;
-    let mut oa = OArray::from(buffer.as_str());
-    oa.target_t = env::args()
-        .nth(1)
-        .expect("Fornire parametro t come argomento")
+use clap::{App, Arg};
+use oarray::FitnessFunction::*;
+use oarray::{open_oarrays, OArray};
+use record::Record;
+use std::io;
+
+fn main() -> io::Result<()> {
+    let matches = App::new("is_linear")
+        .about("Checks the linearity and the fitness values of each OA in the input")
+        .arg(
+            Arg::with_name("t")
+                .help("the strength of the OA")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("files")
+                .help("OA files, with arrays separated by blank lines or ---. Use `-` for standard input")
+                .multiple(true)
+                .default_value("-"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print one JSON record per array"),
+        )
+        .get_matches();
+    let t: u32 = matches
+        .value_of("t")
+        .unwrap()
         .parse()
-        .unwrap();
-    println!("t = {}", oa.target_t);
+        .expect("Invalid value for t");
+    let json = matches.is_present("json");
+    let mut index = 0;
+    for f in matches.values_of_os("files").unwrap() {
+        for oa in open_oarrays(f)? {
+            let mut oa = match oa {
+                Ok(oa) => oa,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                    eprintln!("{}: {}", f.to_string_lossy(), e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            oa.target_t = t;
+            check(&mut oa, index, json);
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn check(oa: &mut OArray, index: usize, json: bool) {
     let linear = oa.check_linear();
-    if linear {
-        println!("Linear");
+    let mut r = Record::new();
+    r.field("index", &index).field("t", &oa.target_t).field("linear", &linear);
+    if !json {
+        if index > 0 {
+            println!();
+        }
+        println!("t = {}", oa.target_t);
+        if linear {
+            println!("Linear");
+        } else {
+            println!("Not linear");
+        }
+    }
+    for &f in &[WalshRec(2), DeltaFast, WalshFaster(2), Walsh(2), Delta, SheerLuck, Cidev] {
+        oa.fitness_f = f;
+        let fitness = oa.fitness();
+        if json {
+            r.field(&format!("{:?}", f), &fitness);
+        } else {
+            println!("{:?}: {}", f, fitness);
+        }
+    }
+    let wtf = oa.walsh_tform();
+    if json {
+        r.field("nonlinearity", &wtf.nonlinearity());
+        println!("{}", r);
     } else {
-        println!("Not linear");
+        println!("Nonlinearity: {}", wtf.nonlinearity());
     }
-    use oarray::FitnessFunction::*;
-    oa.fitness_f = WalshRec(2);
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = DeltaFast;
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = WalshFaster(2);
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = Walsh(2);
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = Delta;
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = SheerLuck;
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    oa.fitness_f = Cidev;
-    println!("{:?}: {}", oa.fitness_f, oa.fitness());
-    let wtf = PolarTruthTable::from(&oa.truth_table()).walsh_tform();
-    println!("Nonlinearity: {}", wtf.nonlinearity());
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,34 @@
+//! Record JSON su una riga, uno per array analizzato.
+
+use serde::Serialize;
+use serde_json::{self, Value};
+use std::fmt::{Display, Error, Formatter};
+
+/// I campi restano nell'ordine in cui sono aggiunti
+#[derive(Default)]
+pub struct Record {
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    pub fn new() -> Self {
+        Record::default()
+    }
+    /// I valori non finiti diventano `null`
+    pub fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> &mut Self {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.fields.push((name.to_string(), value));
+        self
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}:{}", Value::from(name.as_str()), value))
+            .collect();
+        write!(f, "{{{}}}", fields.join(","))
+    }
+}
@@ -3,15 +3,16 @@ extern crate oarray;
 
 use clap::{App, Arg};
 
-use oarray::OArray;
-use std::fs::read_to_string;
+use oarray::{open_oarrays, OArray};
+use std::ffi::OsStr;
+use std::io;
 use std::process::exit;
 
 fn main() {
     let matches = App::new("stat_eq")
         .version("0.1.0")
         .author("Pietro Brenna <p.brenna2@campus.unimib.it>")
-        .about("Checks statistical equivalence. Exit status 0: equivalent; 1: not equivalent")
+        .about("Checks statistical equivalence of the arrays of two files, pairing them in order. Exit status 0: all equivalent; 1: not equivalent")
         .arg(
             Arg::with_name("file1")
                 .takes_value(true)
@@ -28,22 +29,38 @@ fn main() {
 
     let f1 = matches.value_of_os("file1").unwrap();
     let f2 = matches.value_of_os("file2").unwrap();
-    let f1content = read_to_string(f1).unwrap();
-    let f2content = read_to_string(f2).unwrap();
-    let mut oa1 = OArray::from(f1content.as_str());
-    oa1.sort_rows(None);
-    let mut oa2 = OArray::from(f2content.as_str());
-    oa2.sort_rows(None);
-    let eq = if oa1.ngrande != oa2.ngrande || oa1.k != oa2.k {
-        false
-    } else {
-        oa1.d == oa2.d
-    };
-    if eq {
-        println!("Statisticamente equivalenti");
-        exit(0);
-    } else {
-        println!("Non statisticamente equivalenti");
+    let oas1: Vec<OArray> = read_all(f1);
+    let oas2: Vec<OArray> = read_all(f2);
+    if oas1.len() != oas2.len() {
+        println!("Numero di array diverso: {} e {}", oas1.len(), oas2.len());
         exit(1);
     }
+    let multiple = oas1.len() > 1;
+    let mut all_eq = true;
+    for (i, (mut oa1, mut oa2)) in oas1.into_iter().zip(oas2).enumerate() {
+        oa1.sort_rows(None);
+        oa2.sort_rows(None);
+        let eq = if oa1.ngrande != oa2.ngrande || oa1.k != oa2.k {
+            false
+        } else {
+            oa1.d == oa2.d
+        };
+        let prefix = if multiple { format!("{}: ", i) } else { String::new() };
+        if eq {
+            println!("{}Statisticamente equivalenti", prefix);
+        } else {
+            println!("{}Non statisticamente equivalenti", prefix);
+        }
+        all_eq &= eq;
+    }
+    exit(if all_eq { 0 } else { 1 });
+}
+
+/// Tutti gli array del file; termina il programma se non è leggibile
+fn read_all(f: &OsStr) -> Vec<OArray> {
+    let oas: io::Result<Vec<OArray>> = open_oarrays(f).and_then(|r| r.collect());
+    oas.unwrap_or_else(|e| {
+        eprintln!("{}: {}", f.to_string_lossy(), e);
+        exit(2);
+    })
 }
@@ -1,35 +1,73 @@
 extern crate clap;
 extern crate oarray;
+extern crate serde;
+extern crate serde_json;
+
+mod record;
 
 use clap::{App, Arg};
 
-use oarray::OArray;
-use std::fs::read_to_string;
-use std::io::{self, Read};
+use oarray::open_oarrays;
+use record::Record;
+use std::io;
 
-fn main() ->  io::Result<()> {
+fn main() -> io::Result<()> {
     let matches = App::new("w_distr")
         .version("0.1.0")
         .author("Pietro Brenna <p.brenna2@campus.unimib.it>")
         .about("Calculate weight distributions")
         .arg(
-            Arg::with_name("file")
+            Arg::with_name("files")
                 .takes_value(true)
-                .help("OA file. Use `-` for standard input")
+                .multiple(true)
+                .help("OA files, with arrays separated by blank lines or ---. Use `-` for standard input")
                 .required(true),
         )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print one JSON record per array"),
+        )
         .get_matches();
 
-    let f = matches.value_of_os("file").unwrap();
-    let fcontent = if f == "-" {
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer)?;
-        buffer
-    } else {
-        read_to_string(f)?
-    };
-    let oa = OArray::from(fcontent.as_str());
-    println!("Zero: {:?}
-Proper: {:?}", oa.zero_weight_d(), oa.proper_weight_d());
+    let json = matches.is_present("json");
+    let mut index = 0;
+    for f in matches.values_of_os("files").unwrap() {
+        for oa in open_oarrays(f)? {
+            let oa = match oa {
+                Ok(oa) => oa,
+                Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                    eprintln!("{}: {}", f.to_string_lossy(), e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if json {
+                let mut r = Record::new();
+                r.field("index", &index)
+                    .field("zero", &oa.zero_weight_d())
+                    .field("proper", &oa.proper_weight_d())
+                    .field("dual", &oa.dual_weight_d())
+                    .field("dual_distance", &oa.dual_distance())
+                    .field("strength", &oa.strength())
+                    .field("dual_check", &oa.check_dual_distribution());
+                println!("{}", r);
+            } else {
+                if index > 0 {
+                    println!();
+                }
+                println!("Zero: {:?}
+Proper: {:?}
+Dual: {:?}", oa.zero_weight_d(), oa.proper_weight_d(), oa.dual_weight_d());
+                match oa.dual_distance() {
+                    Some(d) => println!("Dual distance: {}", d),
+                    None => println!("Dual distance: none (complete array)"),
+                }
+                println!("Strength: {}", oa.strength());
+                println!("Dual distribution: {}", if oa.check_dual_distribution() { "ok" } else { "inconsistent" });
+            }
+            index += 1;
+        }
+    }
     Ok(())
 }